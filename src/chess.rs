@@ -2,13 +2,43 @@ pub const BLACK_TILE:Color = Color::Rgba{red: 0.462745, green: 0.588235, blue:0.
 pub const WHITE_TILE:Color = Color::Rgba{red: 0.933333, green: 0.933333, blue:0.823529, alpha:1.0};
 pub const PIECE_SCALE:f32 = 0.0078125;
 
-pub mod board;
-
 pub mod promotion;
 
 pub mod arrow;
 
-use std::f32::consts::PI;
+pub mod clock;
+
+pub mod movelist;
+
+pub mod takeback;
+
+pub mod hint;
+
+pub mod select;
+
+pub mod theme;
+
+pub mod animation;
+
+pub mod pieceset;
+
+pub mod orientation;
+
+pub mod sound;
+
+pub mod pgn;
+
+pub mod gameover;
+
+pub mod resign;
+
+pub mod setup;
+
+pub mod notice;
+
+pub mod buttons;
+
+use std::{collections::HashMap, sync::Arc, time::Duration};
 
 use bevy::{
     prelude::*,
@@ -21,25 +51,116 @@ use bevy_dragndrop::*;
 
 use crate::chess::promotion::{PromotionChoiceEvent,PromotionChosenEvent,PromotionPlugin};
 
-use crate::chess::board::{Board,Piece,PieceColor,PieceType,GameOverState};
+use crate::core::board::{Board,Piece,PieceColor,PieceType,GameOverState,Variant};
+use crate::core::square::{File,Rank,Square};
+use crate::core::uci::{EngineError,EngineFallback,EngineSettings};
 use crate::chess::arrow::*;
+use crate::core::book::{BookSettings,OpeningBook};
+use crate::core::syzygy::{Tablebase,TablebaseSettings,open_tablebase};
+use crate::chess::clock::{ClockPlugin,ClockText,GameClock,TimeControl};
+use crate::chess::movelist::{BoardView,MoveListPlugin};
+use crate::chess::takeback::TakebackPlugin;
+use crate::chess::hint::{MoveHint,handle_hints_dragged,clear_hints};
+use crate::chess::select::SelectionPlugin;
+use crate::chess::theme::{BoardTheme,CoordinateText,ThemePlugin,blend};
+use crate::chess::animation::{AnimationPlugin,FadeOut,PieceAnimation};
+use crate::chess::pieceset::{PieceSetPlugin,PieceSprite};
+use crate::chess::orientation::{BoardOrientation,OrientationPlugin,Oriented};
+use crate::chess::sound::{SoundPlugin,SoundSettings};
+use crate::chess::gameover::GameOverPlugin;
+use crate::chess::resign::{DrawOffer,DrawPolicy,OfferReply,ResignPlugin};
+use crate::chess::setup::{SetupPlugin,StartPosition,setup_open};
+use crate::chess::notice::NoticePlugin;
+use crate::chess::takeback::TakebackHistory;
+use crate::chess::select::Selection;
 
 use bevy_prototype_lyon::prelude::*;
+use serde::{Deserialize, Serialize};
 
+//The board after the computer's move, and what went wrong if the engine had to be stood in for.
 #[derive(Component)]
-struct MoveFetch(Task<Board>);
+pub struct MoveFetch(Task<(Board, Option<EngineError>)>);
+
+#[derive(Resource)]
+pub struct BookHolder{
+    pub book: Option<Arc<OpeningBook>>,
+}
+
+#[derive(Resource)]
+pub struct TablebaseHolder{
+    pub tablebase: Option<Arc<Tablebase>>,
+}
 
 #[derive(Event)]
 pub struct AwaitMove;
 
+//The engine gave no move, so the fallback was used in its place.
+#[derive(Event)]
+pub struct EngineFailed{
+    pub error: EngineError,
+    pub fallback: EngineFallback,
+}
+
 pub struct ChessPluginClient{
-    pub settings: ChessPluginSettings
+    pub settings: ChessPluginSettings,
+    //A game to carry on with, such as one read from a PGN, instead of starting a new one.
+    pub game: Option<Board>,
+}
+
+//Which engine plays when the game is against the computer.
+#[derive(Clone,Copy,PartialEq,Debug)]
+pub enum EngineKind{
+    //Skill level from 0 to 20.
+    Stockfish{skill: u8},
+    //The built-in search, looking this many plies ahead.
+    Builtin{depth: u32},
 }
 
-#[derive(Resource,Clone,Copy)]
+//Room left around the board for the move list and buttons, in percent of the window.
+#[derive(Clone,Copy,PartialEq,Debug,Serialize,Deserialize)]
+pub struct Margins{
+    pub left: f32,
+    pub right: f32,
+    pub bottom: f32,
+    pub top: f32,
+}
+
+#[derive(Resource,Clone)]
 pub struct ChessPluginSettings{
     pub color: PieceColor,
+    //Play against the computer. Otherwise both sides are played at this screen.
     pub engine: bool,
+    pub engine_kind: EngineKind,
+    pub engine_settings: EngineSettings,
+    pub book: Option<BookSettings>,
+    pub tablebase: Option<TablebaseSettings>,
+    //Show evaluation readouts such as tablebase results next to the board.
+    pub analysis: bool,
+    pub time_control: Option<TimeControl>,
+    pub takebacks: bool,
+    //Mark where a piece can go while it is being dragged.
+    pub move_hints: bool,
+    pub theme: BoardTheme,
+    //How long a piece takes to slide to its new square. Zero turns animations off.
+    pub animation_duration: Duration,
+    //Name of the folder in assets/textures/pieces the pieces are drawn from.
+    pub piece_set: String,
+    //None plays no sounds at all.
+    pub sounds: Option<SoundSettings>,
+    //How the engine answers the player's draw offers.
+    pub draw_policy: DrawPolicy,
+    pub start_position: StartPosition,
+    pub variant: Variant,
+    //Open the new game menu at startup instead of going straight into a game.
+    pub setup_menu: bool,
+    pub margins: Margins,
+}
+
+impl ChessPluginSettings {
+    //Whether the player at the screen moves this color's pieces.
+    pub fn plays(&self, color: PieceColor) -> bool {
+        !self.engine || color == self.color
+    }
 }
 
 impl Plugin for ChessPluginClient {
@@ -47,30 +168,94 @@ impl Plugin for ChessPluginClient {
         app
         .add_plugins(DragPlugin)
         .add_plugins(PromotionPlugin)
+        .add_plugins(ClockPlugin)
+        .add_plugins(MoveListPlugin)
+        .add_plugins(TakebackPlugin)
+        .add_plugins(SelectionPlugin)
+        .add_plugins(AnimationPlugin)
+        .add_plugins(ThemePlugin)
+        .add_plugins(PieceSetPlugin)
+        .add_plugins(OrientationPlugin)
+        .add_plugins(SoundPlugin)
+        .add_plugins(GameOverPlugin)
+        .add_plugins(ResignPlugin)
+        .add_plugins(SetupPlugin)
+        .add_plugins(NoticePlugin)
         .add_plugins(ShapePlugin)
-        .insert_resource(self.settings)
-        .insert_resource(Board::new())
+        .insert_resource(self.settings.clone())
+        .insert_resource(self.settings.theme.clone())
+        .insert_resource(BoardOrientation{bottom: self.settings.color})
+        .insert_resource(self.game.clone().unwrap_or_else(|| self.settings.start_position.board(self.settings.variant).unwrap_or_else(|e| {
+            println!("Unable to set up the position: {}", e);
+            Board::new()
+        })))
+        .insert_resource(GameClock::new(self.settings.time_control.clone()))
         .insert_resource(PieceTextureHolder{textures: None})
+        .insert_resource(load_book(&self.settings))
+        .insert_resource(load_tablebase(&self.settings))
         .insert_resource(Msaa::Sample4)
         .add_event::<BoardChange>()
+        .add_event::<IllegalMove>()
+        .add_event::<NewGame>()
         .add_event::<AwaitMove>()
+        .add_event::<EngineFailed>()
         .add_systems(Startup, setup_client)
-        .add_systems(Update, (updateboardstate,on_dropped,on_dragged,draw_arrows.after(on_hovered),on_hovered,get_opposing_move,clear_arrows.after(on_dropped)));
+        .add_systems(Update, (updateboardstate.after(adjudicate).after(on_dropped),adjudicate.after(new_game),new_game.before(get_opposing_move),update_analysis,on_dropped,on_dragged,draw_arrows.after(on_hovered),on_hovered,get_opposing_move.run_if(not(setup_open)),clear_arrows.after(on_dropped)));
+    }
+}
+
+fn load_book(settings: &ChessPluginSettings) -> BookHolder {
+    let Some(book_settings) = &settings.book else {
+        return BookHolder{book: None};
+    };
+    match OpeningBook::load(&book_settings.path) {
+        Ok(book) => BookHolder{book: Some(Arc::new(book))},
+        Err(e) => {
+            println!("Unable to load opening book {}: {}", book_settings.path, e);
+            BookHolder{book: None}
+        }
+    }
+}
+
+fn load_tablebase(settings: &ChessPluginSettings) -> TablebaseHolder {
+    let Some(tablebase_settings) = &settings.tablebase else {
+        return TablebaseHolder{tablebase: None};
+    };
+    match open_tablebase(&tablebase_settings.path) {
+        Ok(tablebase) => {
+            if tablebase.max_pieces() == 0 {
+                println!("No tablebases found in {}", tablebase_settings.path);
+            }
+            TablebaseHolder{tablebase: Some(tablebase)}
+        },
+        Err(e) => {
+            println!("Unable to open tablebases in {}: {}", tablebase_settings.path, e);
+            TablebaseHolder{tablebase: None}
+        }
     }
 }
 
-fn get_opposing_move(
+pub fn get_opposing_move(
     mut commands: Commands, 
     mut board: ResMut<Board>, 
     mut er_awaitmove: EventReader<AwaitMove>, 
     mut ew_board: EventWriter<BoardChange>, 
+    mut ew_failed: EventWriter<EngineFailed>,
     settings: Res<ChessPluginSettings>,
+    book_holder: Res<BookHolder>,
+    tablebase_holder: Res<TablebaseHolder>,
     mut q_fetch: Query<(&mut MoveFetch, Entity)>,
 ){
     for (mut task, entity) in &mut q_fetch {
-        if let Some(new_board) = block_on(future::poll_once(&mut task.0)){
-            *board = new_board;
-            ew_board.send(BoardChange);
+        if let Some((new_board, error)) = block_on(future::poll_once(&mut task.0)){
+            //The game may have ended while the engine was thinking, e.g. on time.
+            if board.outcome.is_none() {
+                *board = new_board;
+                ew_board.send(BoardChange);
+                if let Some(error) = error {
+                    ew_failed.send(EngineFailed{error, fallback: settings.engine_settings.fallback});
+                }
+            }
             commands.entity(entity).despawn();
         }
     }
@@ -79,11 +264,29 @@ fn get_opposing_move(
         let entity = commands.spawn_empty().id();
         let mut board_clone = board.clone();
         let settings_clone = settings.clone();
+        let book = book_holder.book.clone();
+        let tablebase = tablebase_holder.tablebase.clone();
         let task = thread_pool.spawn(async move {
+            let mut failure = None;
             if settings_clone.engine {
-                board_clone.engine_move();
+                let book_move = match (&book, &settings_clone.book) {
+                    (Some(book), Some(book_settings)) => book.book_move(&board_clone, book_settings),
+                    _ => None,
+                };
+                let tablebase_move = tablebase.as_ref().and_then(|tablebase| tablebase.best_move(&board_clone));
+                if let Some(bmove) = book_move.or(tablebase_move) {
+                    board_clone.make_simple_move(&bmove);
+                } else {
+                    match settings_clone.engine_kind {
+                        EngineKind::Stockfish{skill} => if let Err(e) = board_clone.engine_move(&settings_clone.engine_settings, skill) {
+                            board_clone.fallback_move(settings_clone.engine_settings.fallback);
+                            failure = Some(e);
+                        },
+                        EngineKind::Builtin{depth} => board_clone.builtin_move(depth),
+                    }
+                }
             }
-            board_clone
+            (board_clone, failure)
         });
 
         commands.entity(entity).insert(MoveFetch(task));
@@ -93,28 +296,10 @@ fn get_opposing_move(
 fn setup_client(
     mut commands: Commands, 
     asset_server: Res<AssetServer>, 
-    mut texture_holder: ResMut<PieceTextureHolder>, 
     mut ev_board: EventWriter<BoardChange>, 
-    settings: Res<ChessPluginSettings>, 
     board: Res<Board>,
-    mut ew_awaitmove: EventWriter<AwaitMove>,
+    theme: Res<BoardTheme>,
 ){
-    texture_holder.textures = Some(PieceTextures { 
-        black_king: asset_server.load("textures/pieces/black/king.png"),
-        black_queen: asset_server.load("textures/pieces/black/queen.png"), 
-        black_rook: asset_server.load("textures/pieces/black/rook.png"), 
-        black_bishop: asset_server.load("textures/pieces/black/bishop.png"), 
-        black_knight: asset_server.load("textures/pieces/black/knight.png"), 
-        black_pawn: asset_server.load("textures/pieces/black/pawn.png"), 
-        white_king: asset_server.load("textures/pieces/white/king.png"), 
-        white_queen: asset_server.load("textures/pieces/white/queen.png"), 
-        white_rook: asset_server.load("textures/pieces/white/rook.png"), 
-        white_bishop: asset_server.load("textures/pieces/white/bishop.png"), 
-        white_knight: asset_server.load("textures/pieces/white/knight.png"), 
-        white_pawn: asset_server.load("textures/pieces/white/pawn.png") 
-    });
-
-
     commands.spawn(Camera2dBundle::default());
 
     let mut tiles: Vec<[Entity;8]> = Vec::new();
@@ -122,8 +307,7 @@ fn setup_client(
     .spawn(SpriteBundle{
         transform: Transform {
             scale: Vec3::new(100.0,100.0,1.0),
-            translation: Vec3::new(0.0,0.0,0.0),
-            rotation: Quat::from_rotation_z(if let PieceColor::Black = settings.color {PI} else {0.}),
+            ..default()
         },
         ..default()
     })
@@ -131,15 +315,15 @@ fn setup_client(
         for y in 0..8 {
             let mut file: Vec<Entity> = Vec::new();
             for x in 0..8 {
-                let tile_num = y + x;
-                let col = if tile_num % 2 == 0 {BLACK_TILE} else {WHITE_TILE};
+                let square = Square::new(File::ALL[x], Rank::ALL[y]);
+                let col = theme.tile_color(square);
                 let x_coord: f32 = (x as f32 * 0.125) - 0.4375;
                 let y_coord: f32 = (y as f32 * 0.125) - 0.4375;
                 let child = parent.spawn(SpriteBundle{
                     transform: Transform {
                         scale: Vec3::new(0.125,0.125,1.0),
                         translation: Vec3::new(x_coord,y_coord,1.0),
-                        rotation: Quat::from_rotation_z(if let PieceColor::Black = settings.color {PI} else {0.}),
+                        ..default()
                     },
                     sprite: Sprite{
                         color: col,
@@ -147,8 +331,10 @@ fn setup_client(
                     },
                     ..default()
                 })
-                .insert(TileEntity{index_x: y, index_y: x})
+                .insert(TileEntity{square})
+                .insert(Oriented::fixed(Vec3::new(x_coord,y_coord,1.0)))
                 .insert(Receiver)
+                .with_children(spawn_arrow_handle)
                 .id();
                 file.push(child);
             }
@@ -161,37 +347,60 @@ fn setup_client(
             font_size: 60.0,
             color: Color::WHITE,
         };
+        let coordinate_style = TextStyle {
+            color: theme.coordinates,
+            ..text_style.clone()
+        };
         let text_alignment = TextAlignment::Center;
 
-        for x_base in 0..8 {
-            let mut x = x_base;
-            let mut x_coord = (x as f32 * 0.125) - 0.4375;
-            let mut y_coord = -0.55;
-            if let PieceColor::Black = settings.color {
-                x_coord = -x_coord;
-                y_coord = -y_coord;
-                x = 7 - x;
-            }
+        //Labels sit below and left of the board, whichever side is at the bottom.
+        for x in 0..8u8 {
+            let coord = (x as f32 * 0.125) - 0.4375;
             parent.spawn(Text2dBundle {
-                text: Text::from_section((97u8 + x) as char, text_style.clone()).with_alignment(text_alignment),
+                text: Text::from_section((97u8 + x) as char, coordinate_style.clone()).with_alignment(text_alignment),
                 transform: Transform { 
-                    translation: Vec3::new(x_coord, y_coord,3.0),
                     scale: Vec3::new(0.001,0.001,1.0),
-                    rotation: Quat::from_rotation_z(if let PieceColor::Black = settings.color {PI} else {0.}),
+                    ..default()
                 },
                 ..default()
-            }
-            );
+            })
+            .insert(Oriented::new(Vec3::new(coord, -0.55, 3.0), Vec3::new(coord, 0.55, 3.0)))
+            .insert(CoordinateText);
             parent.spawn(Text2dBundle {
-                text: Text::from_section((49u8 + x) as char, text_style.clone()).with_alignment(text_alignment),
+                text: Text::from_section((49u8 + x) as char, coordinate_style.clone()).with_alignment(text_alignment),
                 transform: Transform { 
-                    translation: Vec3::new(y_coord, x_coord,3.0),
                     scale: Vec3::new(0.001,0.001,1.0),
-                    rotation: Quat::from_rotation_z(if let PieceColor::Black = settings.color {PI} else {0.}),
+                    ..default()
                 },
                 ..default()
-            }
-            );
+            })
+            .insert(Oriented::new(Vec3::new(-0.55, coord, 3.0), Vec3::new(0.55, coord, 3.0)))
+            .insert(CoordinateText);
+        }
+
+        parent.spawn(Text2dBundle {
+            text: Text::from_section("", text_style.clone()).with_alignment(text_alignment),
+            transform: Transform {
+                scale: Vec3::new(0.0006,0.0006,1.0),
+                ..default()
+            },
+            ..default()
+        })
+        .insert(Oriented::new(Vec3::new(0.0, 0.55, 3.0), Vec3::new(0.0, -0.55, 3.0)))
+        .insert(AnalysisText);
+
+        //The clock of whoever sits at the bottom of the board goes next to the bottom.
+        for (color, y_coord) in [(PieceColor::White, -0.45), (PieceColor::Black, 0.45)] {
+            parent.spawn(Text2dBundle {
+                text: Text::from_section("", text_style.clone()).with_alignment(text_alignment),
+                transform: Transform {
+                    scale: Vec3::new(0.0012,0.0012,1.0),
+                    ..default()
+                },
+                ..default()
+            })
+            .insert(Oriented::new(Vec3::new(0.68, y_coord, 3.0), Vec3::new(-0.68, y_coord, 3.0)))
+            .insert(ClockText(color));
         }
     })
     .insert(BoardEntity{tiles: tiles.try_into().expect("Should initialize with correct size")});
@@ -199,28 +408,46 @@ fn setup_client(
     ev_board.send(BoardChange);
 
     println!("{}", board.fen());
-
-    if let PieceColor::Black = settings.color {
-        ew_awaitmove.send(AwaitMove);
-    }
-    
+    //The BoardChange above has updateboardstate start the engine's search if it is the engine to move.
 }
 
 
 #[derive(Event)]
 pub struct BoardChange;
 
+//A piece was dropped on a square it can't move to.
+#[derive(Event)]
+pub struct IllegalMove;
+
+//Start over with the current settings, or from the same position in a rematch.
+#[derive(Event)]
+pub struct NewGame{
+    //Give the player the other color, as in a rematch.
+    pub swap_colors: bool,
+}
+
 #[derive(Component)]
 pub struct BoardEntity{
     tiles: [[Entity;8];8],
 }
 
+impl BoardEntity {
+    pub fn tile(&self, square: Square) -> Entity {
+        self.tiles[square.rank().index()][square.file().index()]
+    }
+}
+
 #[derive(Component)]
 pub struct TileEntity{
-    index_x: usize,
-    index_y: usize,
+    square: Square,
 }
 
+#[derive(Component)]
+pub struct AnalysisText;
+
+#[derive(Component)]
+pub struct CheckMarker;
+
 #[derive(Component)]
 pub struct PieceEntity{
     piece: Piece,
@@ -248,6 +475,24 @@ pub struct PieceTextures{
 }
 
 impl PieceTextures{
+    pub fn from_fn(mut texture: impl FnMut(Piece) -> Handle<Image>) -> Self {
+        let mut get = |color, piece| texture(Piece{piece, color});
+        PieceTextures {
+            black_king: get(PieceColor::Black, PieceType::King),
+            black_queen: get(PieceColor::Black, PieceType::Queen),
+            black_rook: get(PieceColor::Black, PieceType::Rook),
+            black_bishop: get(PieceColor::Black, PieceType::Bishop),
+            black_knight: get(PieceColor::Black, PieceType::Knight),
+            black_pawn: get(PieceColor::Black, PieceType::Pawn),
+            white_king: get(PieceColor::White, PieceType::King),
+            white_queen: get(PieceColor::White, PieceType::Queen),
+            white_rook: get(PieceColor::White, PieceType::Rook),
+            white_bishop: get(PieceColor::White, PieceType::Bishop),
+            white_knight: get(PieceColor::White, PieceType::Knight),
+            white_pawn: get(PieceColor::White, PieceType::Pawn),
+        }
+    }
+
     pub fn get_texture(&self, piece: Piece) -> Handle<Image>{
         match piece.color{
             PieceColor::Black => {
@@ -279,118 +524,241 @@ fn updateboardstate(
     board: Res<Board>, 
     board_ents: Query<&BoardEntity>, 
     texture_holder: Res<PieceTextureHolder>, 
-    tile_query: Query<&Children,With<TileEntity>>, 
+    mut q_tiles: Query<(&TileEntity, &Transform, &mut Sprite)>,
+    mut q_pieces: Query<(Entity, &mut PieceEntity, &Parent, &mut Transform, &mut Handle<Image>, Has<PieceAnimation>), Without<TileEntity>>,
+    q_check: Query<(Entity, &Parent), With<CheckMarker>>,
     mut er_board: EventReader<BoardChange>,
     asset_server: Res<AssetServer>,
     settings: Res<ChessPluginSettings>,
     mut ew_awaitmove: EventWriter<AwaitMove>,
+    view: Res<BoardView>,
+    q_fetch: Query<(), With<MoveFetch>>,
+    theme: Res<BoardTheme>,
 ){
+    //Redraw once, however many changes came in, since the piece entities are only moved once the frame ends.
+    if er_board.read().last().is_none() {return;}
     let check_image: Handle<Image> = asset_server.load("textures/check.png");
-    for _ in er_board.read() {
-        let board_ent = match board_ents.iter().last() {
-            Some(x) => x,
-            None => return,
+    let board_ent = match board_ents.iter().last() {
+        Some(x) => x,
+        None => return,
+    };
+    let Some(textures) = &texture_holder.textures else {return};
+    //May be an earlier position of the game if the player is looking back through the moves.
+    let position = view.position(&board);
+
+    //Piece entities persist between positions, so work out which of them moved where.
+    let mut shown: HashMap<Square, (Entity, Piece)> = HashMap::new();
+    for (entity, piece_ent, parent, ..) in q_pieces.iter() {
+        if let Ok((tile, ..)) = q_tiles.get(parent.get()) {
+            shown.insert(tile.square, (entity, piece_ent.piece));
+        }
+    }
+    let mut vanished: Vec<(Square, Entity, Piece)> = Vec::new();
+    let mut appeared: Vec<(Square, Piece)> = Vec::new();
+    for square in Square::all() {
+        match (shown.get(&square).copied(), position[square]) {
+            (Some((entity, old)), Some(new)) if old == new => {
+                //Put back pieces that were dropped on an illegal square.
+                if let Ok((_, _, _, mut transform, _, false)) = q_pieces.get_mut(entity) {
+                    transform.translation = Vec3::new(0.0,0.0,2.0);
+                }
+            },
+            (old, new) => {
+                if let Some((entity, piece)) = old {vanished.push((square, entity, piece));}
+                if let Some(piece) = new {appeared.push((square, piece));}
+            },
+        }
+    }
+    for (to, piece) in appeared {
+        //The last move says which piece got here, even if it was promoted on the way.
+        let moved = position.lastmove
+            .filter(|lastmove| lastmove.to == to)
+            .and_then(|lastmove| vanished.iter().position(|(square, _, old)| *square == lastmove.from && *old == lastmove.piece));
+        let nearest = vanished.iter().enumerate()
+            .filter(|(_, (_, _, old))| *old == piece)
+            .min_by_key(|(_, (square, _, _))| square.file().distance(to.file()).max(square.rank().distance(to.rank())))
+            .map(|(i, _)| i);
+        let tile_ent = board_ent.tile(to);
+        let Some(i) = moved.or(nearest) else {
+            commands.entity(tile_ent).with_children(|parent| {
+                spawn_piece(parent, piece, textures.get_texture(piece));
+            });
+            continue;
         };
-        println!("Gamestate: {:?}", board.is_gameover());
-        for x in 0..8 {
-            for y in 0..8 {
-                let tile_ent = board_ent.tiles[x][y];
-                match tile_query.get(tile_ent) {
-                    Ok(children) => {
-                        for &child in children.iter(){
-                            commands.entity(tile_ent).remove_children(&[child]);
-                            commands.entity(child).despawn();
-                        }
-                    },
-                    Err(_) => (),
-                };
-                
-                let texture = match board.tiles[x][y] {
-                    Some(piece) => {
-                        match &texture_holder.textures {
-                            Some(textures) => textures.get_texture(piece),
-                            None => continue
-                        }
-                    }
-                    None => {
-                        commands.entity(tile_ent).with_children(|parent| {
-                            parent.spawn((SpriteBundle{
-                                transform: Transform {
-                                    translation: Vec3::new(0.0,0.0,1.0),
-                                    ..default()
-                                },
-                                sprite: Sprite {
-                                    color: Color::rgba(0.0,0.0,0.0,0.0),
-                                    ..default()
-                                },
-                                ..default()
-                            }, Draggable {
-                                required: InputFlags::RightClick,
-                                disallowed: InputFlags::MiddleClick | InputFlags::LeftClick,
-                                minimum_held: Some(0.05)
-                            }, ArrowDraggable{parent: parent.parent_entity(), drawn: None, color: None}));
-                        });
-                        continue;
-                    }
-                };
-                commands.entity(tile_ent).with_children(|parent| {
-                    parent.spawn(SpriteBundle{
-                        transform: Transform {
-                            scale: Vec3::new(PIECE_SCALE,PIECE_SCALE,1.0),
-                            //rotation: Quat::from_rotation_z(if let PieceColor::Black = settings.color {PI} else {0.}),
-                            translation: Vec3::new(0.0,0.0,2.0),
-                            ..default()
-                        },
-                        texture,
-                        ..default()
-                    })
-                    .insert(Draggable {
-                        required: InputFlags::LeftClick,
-                        disallowed: InputFlags::RightClick
-                            | InputFlags::MiddleClick,
-                        minimum_held: Some(0.05),
-                    })
-                    .insert(PieceEntity{ piece:board.tiles[x][y].unwrap()});
-
-                    parent.spawn((SpriteBundle{
-                        transform: Transform {
-                            translation: Vec3::new(0.0,0.0,1.0),
-                            ..default()
-                        },
-                        sprite: Sprite {
-                            color: Color::rgba(0.0,0.0,0.0,0.0),
-                            ..default()
-                        },
-                        ..default()
-                    }, Draggable {
-                        required: InputFlags::RightClick,
-                        disallowed: InputFlags::MiddleClick | InputFlags::LeftClick,
-                        minimum_held: Some(0.05)
-                    }, ArrowDraggable{parent: parent.parent_entity(), drawn: None, color: None}));
-                });
-
+        let (from, entity, _) = vanished.swap_remove(i);
+        let (Ok((_, from_transform, _)), Ok((_, to_transform, _))) = (q_tiles.get(board_ent.tile(from)), q_tiles.get(tile_ent)) else {continue};
+        //Where the piece is now, in the space of the tile it is moving to.
+        let offset = to_transform.rotation.inverse() * (from_transform.translation - to_transform.translation) / to_transform.scale;
+        if let Ok((_, mut piece_ent, _, mut transform, mut texture, _)) = q_pieces.get_mut(entity) {
+            let start = transform.translation.truncate() + offset.truncate();
+            piece_ent.piece = piece;
+            *texture = textures.get_texture(piece);
+            transform.translation = Vec3::new(0.0,0.0,2.0);
+            if !settings.animation_duration.is_zero() {
+                transform.translation = start.extend(10.0);
+                commands.entity(entity).insert(PieceAnimation::new(start, settings.animation_duration));
             }
+            commands.entity(entity).set_parent(tile_ent);
         }
-        for color in [PieceColor::White,PieceColor::Black] {
-            if board.is_check(color) {
-                let king = board.king_coords(color);
-                let tile_ent = board_ent.tiles[king.0][king.1];
-                commands.entity(tile_ent).with_children(|parent| {
-                    parent.spawn(SpriteBundle{
-                        transform: Transform {
-                            scale: Vec3::new(PIECE_SCALE,PIECE_SCALE,1.0),
-                            translation: Vec3::new(0.0,0.0,1.0),
-                            ..default()
-                        },
-                        texture:check_image.clone(),
-                        ..default()
-                    });
-                });
-            }
+    }
+    //Whatever is left was captured.
+    for (_, entity, _) in vanished {
+        if settings.animation_duration.is_zero() {
+            commands.entity(entity).despawn_recursive();
+        } else {
+            commands.entity(entity).remove::<(PieceEntity, Draggable, PieceAnimation)>().insert(FadeOut::new(settings.animation_duration));
         }
-        if board.to_move() != settings.color && settings.engine {
-            ew_awaitmove.send(AwaitMove);
+    }
+
+    for square in Square::all() {
+        let mut color = theme.tile_color(square);
+        if position.lastmove.is_some_and(|lastmove| lastmove.from == square || lastmove.to == square) {
+            color = blend(color, theme.last_move);
         }
+        if let Ok((_, _, mut sprite)) = q_tiles.get_mut(board_ent.tile(square)) {
+            if sprite.color != color {sprite.color = color;}
+        }
+    }
+
+    let mut checks: Vec<Square> = [PieceColor::White,PieceColor::Black].into_iter()
+        .filter(|color| position.is_check(*color))
+        .map(|color| position.king_coords(color))
+        .collect();
+    for (entity, parent) in q_check.iter() {
+        let square = q_tiles.get(parent.get()).ok().map(|(tile, ..)| tile.square);
+        match checks.iter().position(|check| Some(*check) == square) {
+            Some(i) => {checks.swap_remove(i);},
+            None => commands.entity(entity).despawn_recursive(),
+        }
+    }
+    for king in checks {
+        commands.entity(board_ent.tile(king)).with_children(|parent| {
+            parent.spawn((SpriteBundle{
+                transform: Transform {
+                    scale: Vec3::new(PIECE_SCALE,PIECE_SCALE,1.0),
+                    translation: Vec3::new(0.0,0.0,1.0),
+                    ..default()
+                },
+                sprite: Sprite {
+                    color: theme.check,
+                    ..default()
+                },
+                texture:check_image.clone(),
+                ..default()
+            }, CheckMarker));
+        });
+    }
+    //Only one engine search at a time, even if the board is redrawn while it thinks.
+    if q_fetch.is_empty() && board.to_move() != settings.color && settings.engine && board.is_gameover() == GameOverState::Ongoing {
+        ew_awaitmove.send(AwaitMove);
+    }
+}
+
+pub fn piece_draggable() -> Draggable {
+    Draggable {
+        required: InputFlags::LeftClick,
+        disallowed: InputFlags::RightClick
+            | InputFlags::MiddleClick,
+        minimum_held: Some(0.05),
+    }
+}
+
+fn spawn_piece(parent: &mut ChildBuilder, piece: Piece, texture: Handle<Image>) {
+    parent.spawn(SpriteBundle{
+        transform: Transform {
+            scale: Vec3::new(PIECE_SCALE,PIECE_SCALE,1.0),
+            translation: Vec3::new(0.0,0.0,2.0),
+            ..default()
+        },
+        texture,
+        ..default()
+    })
+    .insert(piece_draggable())
+    .insert(PieceSprite{ size: 1.0 })
+    .insert(PieceEntity{ piece });
+}
+
+fn adjudicate(
+    mut board: ResMut<Board>,
+    mut er_board: EventReader<BoardChange>,
+    settings: Res<ChessPluginSettings>,
+    tablebase_holder: Res<TablebaseHolder>,
+) {
+    if er_board.read().last().is_none() || board.outcome.is_some() {return;}
+    //Every ending is recorded, so nothing more can be played once the game is over.
+    let ending = match board.is_gameover() {
+        GameOverState::Ongoing => board.draw_by_rule(),
+        ending => Some(ending),
+    };
+    if ending.is_some() {
+        board.outcome = ending;
+        return;
+    }
+    let Some(tablebase) = &tablebase_holder.tablebase else {return};
+    if !settings.tablebase.as_ref().is_some_and(|tb| tb.adjudicate) {return;}
+    if let Some(outcome) = tablebase.adjudicate(&board) {
+        board.outcome = Some(outcome);
+    }
+}
+
+fn new_game(
+    mut commands: Commands,
+    mut er_new_game: EventReader<NewGame>,
+    mut board: ResMut<Board>,
+    mut settings: ResMut<ChessPluginSettings>,
+    mut clock: ResMut<GameClock>,
+    mut orientation: ResMut<BoardOrientation>,
+    mut view: ResMut<BoardView>,
+    mut history: ResMut<TakebackHistory>,
+    mut selection: ResMut<Selection>,
+    mut offer: ResMut<DrawOffer>,
+    q_fetch: Query<Entity, With<MoveFetch>>,
+    q_pieces: Query<Entity, Or<(With<PieceEntity>, With<CheckMarker>)>>,
+    mut ew_board: EventWriter<BoardChange>,
+) {
+    let Some(event) = er_new_game.read().last() else {return};
+    if event.swap_colors {
+        settings.color = !settings.color;
+    }
+    orientation.bottom = settings.color;
+    //A rematch is played from the same position, even a random Chess960 one.
+    let start = if event.swap_colors {Ok(board.start_position())} else {settings.start_position.board(settings.variant)};
+    *board = start.unwrap_or_else(|e| {
+        println!("Unable to set up the position: {}", e);
+        Board::new()
+    });
+    *clock = GameClock::new(settings.time_control.clone());
+    view.ply = None;
+    history.clear();
+    selection.square = None;
+    offer.pending = None;
+    offer.reply = OfferReply::None;
+    //The engine may still be thinking about the old game.
+    for entity in q_fetch.iter() {
+        commands.entity(entity).despawn();
+    }
+    //Set the new position out from scratch rather than sliding the old pieces into it.
+    for entity in q_pieces.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+    ew_board.send(BoardChange);
+}
+
+fn update_analysis(
+    board: Res<Board>,
+    mut er_board: EventReader<BoardChange>,
+    settings: Res<ChessPluginSettings>,
+    tablebase_holder: Res<TablebaseHolder>,
+    mut q_text: Query<&mut Text, With<AnalysisText>>,
+) {
+    if er_board.read().last().is_none() || !settings.analysis {return;}
+    //Analysis only comes from the tablebases so far, so say why there is none rather than leave the readout blank.
+    let readout = match &tablebase_holder.tablebase {
+        Some(tablebase) => tablebase.describe(&board).unwrap_or_else(|| format!("Not in the tablebases (up to {} pieces)", tablebase.max_pieces())),
+        None => "No tablebases, set a Syzygy path to analyse endgames".to_string(),
+    };
+    for mut text in q_text.iter_mut() {
+        text.sections[0].value = readout.clone();
     }
 }
 
@@ -405,13 +773,25 @@ fn on_dropped(
     mut ew_promotion: EventWriter<PromotionChoiceEvent>,
     mut er_promotion: EventReader<PromotionChosenEvent>,
     q_arrow: Query<&ArrowDraggable>,
-    settings: Res<ChessPluginSettings>
+    settings: Res<ChessPluginSettings>,
+    mut view: ResMut<BoardView>,
+    q_hints: Query<Entity, With<MoveHint>>,
+    mut ew_illegal: EventWriter<IllegalMove>,
 ) {
     let mut events = 0;
+    if !er_drop.is_empty() {
+        clear_hints(&mut commands, &q_hints);
+    }
     for event in er_drop.read() {
         if handle_arrow_dropped(&mut commands, event, &mut transforms, &q_arrow) {continue;}
         if let Some(received) = event.received {
-            if handle_piece_dropped(event, received, &mut board, &piece_ents,  &tile_ents, &mut ew_promotion, &settings) {events += 1;}
+            //Moves can only be made on the live position, so go back to it instead.
+            if !view.is_live() {
+                view.ply = None;
+                events += 1;
+                continue;
+            }
+            if handle_piece_dropped(event, received, &mut board, &piece_ents,  &tile_ents, &mut ew_promotion, &mut ew_illegal, &settings) {events += 1;}
 
             
 
@@ -437,6 +817,7 @@ fn handle_piece_dropped(
     piece_ents: &Query<(&PieceEntity,&Parent)>,
     tile_ents: &Query<&TileEntity>,
     ew_promotion: &mut EventWriter<PromotionChoiceEvent>,
+    ew_illegal: &mut EventWriter<IllegalMove>,
     settings: &Res<ChessPluginSettings>,
 ) -> bool {
     let tile_ent = tile_ents.get(received).unwrap();
@@ -444,17 +825,18 @@ fn handle_piece_dropped(
         return false;
     };
     let parent_tile = tile_ents.get(parent.get()).unwrap();
-    /*if(board.is_legal_move((parent_tile.index_x,parent_tile.index_y),(tile_ent.index_x,tile_ent.index_y))) {
-        board.tiles[parent_tile.index_x][parent_tile.index_y] = None;
-        board.tiles[tile_ent.index_x][tile_ent.index_y] = Some(piece_ent.piece);
-    }*/
-    if piece_ent.piece.color != settings.color {
+    let (from, to) = (parent_tile.square, tile_ent.square);
+    //Putting a piece back where it was is not an attempt to move it.
+    if from != to && (!settings.plays(piece_ent.piece.color) || !board.is_legal_move(from, to)) {
+        ew_illegal.send(IllegalMove);
+    }
+    if !settings.plays(piece_ent.piece.color) {
         return true;
     }
-    if board.can_promote((parent_tile.index_x,parent_tile.index_y), (tile_ent.index_x,tile_ent.index_y)) {
-        ew_promotion.send(PromotionChoiceEvent { color: piece_ent.piece.color, from: (parent_tile.index_x,parent_tile.index_y), to: (tile_ent.index_x,tile_ent.index_y) })
+    if board.can_promote(from, to) {
+        ew_promotion.send(PromotionChoiceEvent { color: piece_ent.piece.color, from, to })
     } else {
-        board.make_move((parent_tile.index_x,parent_tile.index_y), (tile_ent.index_x,tile_ent.index_y), None);
+        board.make_move(from, to, None);
         
         return true;
     }
@@ -468,11 +850,23 @@ fn on_dragged(
     mut q_draggable: Query<&mut Transform, With<Draggable>>,
     q_arrow: Query<&ArrowDraggable>,
     parents: Query<&Parent>,
+    board: Res<Board>,
+    settings: Res<ChessPluginSettings>,
+    view: Res<BoardView>,
+    piece_ents: Query<(&PieceEntity,&Parent)>,
+    tile_ents: Query<&TileEntity>,
+    board_ents: Query<&BoardEntity>,
 ) {
     for event in er_drag.read() {
         let mut transform = q_draggable.get_mut(event.dragged).unwrap();
         transform.translation.z = 15.0;
         handle_arrow_dragged(&mut commands, event, &q_arrow, &parents);
+        commands.entity(event.dragged).remove::<PieceAnimation>();
+        if settings.move_hints && view.is_live() {
+            if let Ok(board_ent) = board_ents.get_single() {
+                handle_hints_dragged(&mut commands, event, &board, &piece_ents, &tile_ents, board_ent);
+            }
+        }
     }
 }
 
@@ -484,10 +878,11 @@ fn on_hovered (
     q_tiles: Query<&TileEntity>,
     mut q_arrow: Query<(&mut ArrowDraggable, Entity)>,
     q_board: Query<Entity, With<BoardEntity>>,
+    theme: Res<BoardTheme>,
 ) {
     if er_hovered.is_empty() {return;}
     let board = q_board.single();
     for event in er_hovered.read() {
-        handle_arrow_hovered(&mut commands, event, &transforms, &q_tiles, &mut q_arrow, board);
+        handle_arrow_hovered(&mut commands, event, &transforms, &q_tiles, &mut q_arrow, board, &theme);
     }
 }
\ No newline at end of file
@@ -0,0 +1,71 @@
+use std::time::Duration;
+
+use bevy::prelude::*;
+
+pub struct AnimationPlugin;
+
+impl Plugin for AnimationPlugin {
+    fn build(&self, app: &mut App) {
+        app
+        .add_systems(Update, (animate_pieces, fade_pieces));
+    }
+}
+
+//A piece sliding into the center of its tile from `start`, in tile space.
+#[derive(Component)]
+pub struct PieceAnimation{
+    start: Vec2,
+    timer: Timer,
+}
+
+impl PieceAnimation {
+    pub fn new(start: Vec2, duration: Duration) -> Self {
+        PieceAnimation {start, timer: Timer::new(duration, TimerMode::Once)}
+    }
+}
+
+//A captured piece disappearing, despawned once it is gone.
+#[derive(Component)]
+pub struct FadeOut{
+    timer: Timer,
+}
+
+impl FadeOut {
+    pub fn new(duration: Duration) -> Self {
+        FadeOut {timer: Timer::new(duration, TimerMode::Once)}
+    }
+}
+
+fn animate_pieces(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut q_animation: Query<(Entity, &mut Transform, &mut PieceAnimation)>,
+) {
+    for (entity, mut transform, mut animation) in q_animation.iter_mut() {
+        animation.timer.tick(time.delta());
+        if animation.timer.finished() {
+            transform.translation = Vec3::new(0.0,0.0,2.0);
+            commands.entity(entity).remove::<PieceAnimation>();
+            continue;
+        }
+        //Ease out, so the piece settles onto its square.
+        let t = 1.0 - (1.0 - animation.timer.percent()).powi(3);
+        //Stay above the pieces it passes over.
+        transform.translation = animation.start.lerp(Vec2::ZERO, t).extend(10.0);
+    }
+}
+
+fn fade_pieces(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut q_fade: Query<(Entity, &mut Sprite, &mut FadeOut)>,
+) {
+    for (entity, mut sprite, mut fade) in q_fade.iter_mut() {
+        fade.timer.tick(time.delta());
+        if fade.timer.finished() {
+            commands.entity(entity).despawn_recursive();
+            continue;
+        }
+        sprite.color.set_a(fade.timer.percent_left());
+    }
+}
@@ -11,6 +11,8 @@ use bevy_prototype_lyon::shapes::Circle;
 use bevy_prototype_lyon::prelude::*;
 
 use super::{TileEntity, BoardEntity};
+use super::theme::BoardTheme;
+use crate::core::square::Square;
 
 #[derive(Component)]
 pub struct ArrowDraggable{
@@ -56,26 +58,29 @@ pub fn handle_arrow_dragged(
 ) {
     if let Ok(_) = q_arrow.get(event.dragged) {
         let parent = parents.get(event.dragged).unwrap().get();
-        commands.entity(parent).with_children(|parent| {
-            parent.spawn((SpriteBundle{
-                transform: Transform {
-                    translation: Vec3::new(0.0,0.0,1.0),
-                    ..default()
-                },
-                sprite: Sprite {
-                    color: Color::rgba(0.0,0.0,0.0,0.0),
-                    ..default()
-                },
-                ..default()
-            }, Draggable {
-                required: InputFlags::RightClick,
-                disallowed: InputFlags::MiddleClick | InputFlags::LeftClick,
-                minimum_held: Some(0.05)
-            }, ArrowDraggable{parent: parent.parent_entity(), drawn: None, color: None}));
-        });
+        commands.entity(parent).with_children(spawn_arrow_handle);
     }
 }
 
+//Invisible handle on a tile that arrows are dragged out of with the right mouse button.
+pub fn spawn_arrow_handle(parent: &mut ChildBuilder) {
+    parent.spawn((SpriteBundle{
+        transform: Transform {
+            translation: Vec3::new(0.0,0.0,1.0),
+            ..default()
+        },
+        sprite: Sprite {
+            color: Color::rgba(0.0,0.0,0.0,0.0),
+            ..default()
+        },
+        ..default()
+    }, Draggable {
+        required: InputFlags::RightClick,
+        disallowed: InputFlags::MiddleClick | InputFlags::LeftClick,
+        minimum_held: Some(0.05)
+    }, ArrowDraggable{parent: parent.parent_entity(), drawn: None, color: None}));
+}
+
 pub fn draw_arrows(
     mut commands: Commands,
     transforms: Query<&Transform>,
@@ -83,6 +88,7 @@ pub fn draw_arrows(
     q_tiles: Query<&TileEntity>,
     q_draggable: Query<&Draggable>,
     q_board: Query<Entity, With<BoardEntity>>,
+    theme: Res<BoardTheme>,
 ) {
     if q_arrow.is_empty() {return;}
     let board = q_board.single();
@@ -95,14 +101,13 @@ pub fn draw_arrows(
                 let p2 = transforms.get(parent).unwrap().translation.xy();
                 let p2_tile = q_tiles.get(parent).unwrap();
 
-                let offset = p1_tile.index_x + (p1_tile.index_y << 3) + (p2_tile.index_x << 6) + (p2_tile.index_y << 9);
-                let offset_float = (offset as f32) / ((1 << 11) as f32);
+                let offset_float = arrow_offset(p1_tile.square, p2_tile.square);
                 println!("{}",offset_float);
 
-                arrow.drawn = Some(draw_arrow(&mut commands, p1, p2, board, entity, arrow.color, offset_float));
+                arrow.drawn = Some(draw_arrow(&mut commands, p1, p2, board, entity, arrow.color.unwrap_or(theme.arrows[0]), offset_float));
             } else if let Err(_) = q_draggable.get(entity) {
                 let p1 = transforms.get(arrow.parent).unwrap().translation.xy();
-                arrow.drawn = Some(draw_circle(&mut commands, p1, board, entity, arrow.color));
+                arrow.drawn = Some(draw_circle(&mut commands, p1, board, entity, arrow.color.unwrap_or(theme.arrows[0])));
             }
         }
     }
@@ -113,9 +118,8 @@ fn draw_circle(
     p1: Vec2,
     board: Entity,
     arrow: Entity,
-    color: Option<Color>,
+    color: Color,
 ) -> Entity {
-    let color = color.unwrap_or(Color::rgba(0.0,1.0,0.0,0.7));
 
     let mut ent = board;
 
@@ -143,20 +147,25 @@ fn draw_circle(
     return ent;
 }
 
+//Depth for an arrow between two squares, different for every pair so crossing arrows don't flicker.
+fn arrow_offset(from: Square, to: Square) -> f32 {
+    let index = |square: Square| square.rank().index() + (square.file().index() << 3);
+    (index(from) + (index(to) << 6)) as f32 / ((1 << 11) as f32)
+}
+
 fn draw_arrow(
     commands: &mut Commands,
     p1: Vec2,
     p2: Vec2,
     board: Entity,
     arrow: Entity,
-    color: Option<Color>,
+    color: Color,
     z_offset: f32,
 ) -> Entity {
     let dif = p2 - p1;
     let dist = (dif.x * dif.x + dif.y * dif.y).sqrt() * 100.;
     let angle = dif.y.atan2(dif.x);
     let mut ent = board;
-    let color = color.unwrap_or(Color::rgba(0.0,1.0,0.0,0.7));
 
     let mut path_builder = PathBuilder::new();
     path_builder.move_to(Vec2::new(0.,0.));
@@ -207,10 +216,11 @@ pub fn handle_arrow_hovered(mut commands: &mut Commands,
     q_tiles: &Query<&TileEntity>,
     q_arrow: &mut Query<(&mut ArrowDraggable, Entity)>,
     board: Entity,
+    theme: &BoardTheme,
 ) {
     if let Ok((mut arrow, ent)) = q_arrow.get_mut(event.hovered) {
         if let None = arrow.color {
-            arrow.color = Some(color_from_inputs(event.inputs));
+            arrow.color = Some(color_from_inputs(event.inputs, theme));
         }
         if let Some(drawn) = arrow.drawn {
             if let Some(mut drawn_ent) = commands.get_entity(drawn) {
@@ -227,10 +237,9 @@ pub fn handle_arrow_hovered(mut commands: &mut Commands,
                 let p2 = transforms.get(over).unwrap().translation.xy();
                 let p2_tile = q_tiles.get(over).unwrap();
 
-                let offset = p1_tile.index_x + (p1_tile.index_y << 3) + (p2_tile.index_x << 6) + (p2_tile.index_y << 9);
-                let offset_float = (offset as f32) / ((1 << 11) as f32);
+                let offset_float = arrow_offset(p1_tile.square, p2_tile.square);
 
-                arrow.drawn = Some(draw_arrow(&mut commands, p1, p2, board, ent, arrow.color, offset_float));
+                arrow.drawn = Some(draw_arrow(&mut commands, p1, p2, board, ent, arrow.color.unwrap_or(theme.arrows[0]), offset_float));
             }
         }
     }
@@ -263,10 +272,10 @@ pub fn clear_arrows(
     }
 }
 
-fn color_from_inputs(inputs: InputFlags) -> Color {
-    if !(inputs.intersects(InputFlags::Modifiers)) {Color::rgba(0.0,1.0,0.0,0.85)}
-    else if inputs.contains(InputFlags::Shift | InputFlags::Alt) {Color::rgba(1.0,1.0,0.0,0.85)}
-    else if inputs.contains(InputFlags::Shift) {Color::rgba(1.0,0.0,0.0,0.85)}
-    else if inputs.contains(InputFlags::Alt) {Color::rgba(0.0,0.0,1.0,0.85)}
-    else {Color::rgba(1.0,0.0,0.0,0.7)}
-}
\ No newline at end of file
+fn color_from_inputs(inputs: InputFlags, theme: &BoardTheme) -> Color {
+    if !(inputs.intersects(InputFlags::Modifiers)) {theme.arrows[0]}
+    else if inputs.contains(InputFlags::Shift | InputFlags::Alt) {theme.arrows[3]}
+    else if inputs.contains(InputFlags::Shift) {theme.arrows[1]}
+    else if inputs.contains(InputFlags::Alt) {theme.arrows[2]}
+    else {theme.arrows[1]}
+}
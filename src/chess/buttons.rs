@@ -0,0 +1,64 @@
+use bevy::prelude::*;
+
+use super::Margins;
+
+//Background of a margin button when left alone, hovered and pressed.
+const IDLE: Color = Color::rgba(0.0, 0.0, 0.0, 0.25);
+const HOVERED: Color = Color::rgba(0.0, 0.0, 0.0, 0.4);
+const PRESSED: Color = Color::rgba(0.0, 0.0, 0.0, 0.6);
+
+//A row of buttons across the right margin, such as resigning above the board and takebacks below it.
+//`top` and `height` are in percent of the window, and each button is tagged with its component.
+pub fn spawn_margin_buttons<B: Component>(
+    commands: &mut Commands,
+    asset_server: &AssetServer,
+    margins: Margins,
+    top: f32,
+    height: f32,
+    buttons: impl IntoIterator<Item = (B, &'static str)>,
+) {
+    let text_style = TextStyle {
+        font: asset_server.load("fonts/FiraSans-Bold.ttf"),
+        font_size: 20.0,
+        color: Color::WHITE,
+    };
+    commands.spawn(NodeBundle {
+        style: Style {
+            position_type: PositionType::Absolute,
+            right: Val::Percent(1.0),
+            top: Val::Percent(top),
+            width: Val::Percent(margins.right - 2.0),
+            height: Val::Percent(height),
+            justify_content: JustifyContent::SpaceBetween,
+            ..default()
+        },
+        ..default()
+    })
+    .with_children(|parent| {
+        for (button, label) in buttons {
+            parent.spawn((ButtonBundle {
+                style: Style {
+                    width: Val::Percent(48.0),
+                    justify_content: JustifyContent::Center,
+                    align_items: AlignItems::Center,
+                    ..default()
+                },
+                background_color: IDLE.into(),
+                ..default()
+            }, button))
+            .with_children(|button| {
+                button.spawn(TextBundle::from_section(label, text_style.clone()));
+            });
+        }
+    });
+}
+
+//Shade a margin button for how the mouse is on it, returning whether it was just pressed.
+pub fn shade_margin_button(interaction: Interaction, color: &mut BackgroundColor) -> bool {
+    *color = match interaction {
+        Interaction::Pressed => PRESSED,
+        Interaction::Hovered => HOVERED,
+        Interaction::None => IDLE,
+    }.into();
+    interaction == Interaction::Pressed
+}
@@ -0,0 +1,276 @@
+use std::{fmt, str::FromStr, time::Duration};
+
+use bevy::prelude::*;
+
+use super::{BoardChange, ChessPluginSettings};
+use super::setup::setup_open;
+use crate::core::board::{Board, GameOverState, PieceColor};
+
+pub struct ClockPlugin;
+
+impl Plugin for ClockPlugin {
+    fn build(&self, app: &mut App) {
+        app
+        .add_systems(Update, (press_clock, tick_clock.after(press_clock).run_if(not(setup_open)), update_clock_text.after(tick_clock)));
+    }
+}
+
+#[derive(Clone,Copy,PartialEq,Debug)]
+pub enum Increment{
+    //Sudden death, no time is ever added.
+    None,
+    //Added after every move.
+    Fischer(Duration),
+    //Time used is given back after the move, up to the delay.
+    Bronstein(Duration),
+    //The clock waits for the delay before it starts counting down.
+    Delay(Duration),
+}
+
+#[derive(Clone,Copy,PartialEq,Debug)]
+pub struct TimePeriod{
+    //Moves to be played within this period, or None if it lasts for the rest of the game.
+    pub moves: Option<u32>,
+    pub time: Duration,
+}
+
+#[derive(Clone,PartialEq,Debug)]
+pub struct TimeControl{
+    pub periods: Vec<TimePeriod>,
+    pub increment: Increment,
+}
+
+impl TimeControl {
+    pub fn sudden_death(time: Duration) -> Self {
+        TimeControl {periods: vec![TimePeriod {moves: None, time}], increment: Increment::None}
+    }
+
+    pub fn fischer(time: Duration, increment: Duration) -> Self {
+        TimeControl {periods: vec![TimePeriod {moves: None, time}], increment: Increment::Fischer(increment)}
+    }
+
+    //A period with a move count that is the last one repeats, as in the PGN TimeControl tag.
+    fn period(&self, index: usize) -> TimePeriod {
+        self.periods[index.min(self.periods.len() - 1)]
+    }
+}
+
+#[derive(Clone,Copy,PartialEq,Debug)]
+pub struct ParseTimeControlError;
+
+impl fmt::Display for ParseTimeControlError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid time control")
+    }
+}
+
+impl std::error::Error for ParseTimeControlError {}
+
+//Uses the PGN TimeControl tag syntax in seconds, e.g. "300+5" or "40/5400:1800+30".
+//Delays are written "+d5" for a simple delay and "+b5" for Bronstein.
+impl FromStr for TimeControl {
+    type Err = ParseTimeControlError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let seconds = |x: &str| x.trim().parse::<f32>().ok().and_then(|x| Duration::try_from_secs_f32(x).ok()).ok_or(ParseTimeControlError);
+        let (periods, increment) = match s.split_once('+') {
+            Some((periods, increment)) => {
+                let increment = if let Some(delay) = increment.strip_prefix('d') {
+                    Increment::Delay(seconds(delay)?)
+                } else if let Some(delay) = increment.strip_prefix('b') {
+                    Increment::Bronstein(seconds(delay)?)
+                } else {
+                    Increment::Fischer(seconds(increment)?)
+                };
+                (periods, increment)
+            },
+            None => (s, Increment::None),
+        };
+        let periods = periods.split(':').map(|period| match period.split_once('/') {
+            Some((moves, time)) => Ok(TimePeriod {moves: Some(moves.trim().parse().map_err(|_| ParseTimeControlError)?), time: seconds(time)?}),
+            None => Ok(TimePeriod {moves: None, time: seconds(period)?}),
+        }).collect::<Result<Vec<_>, _>>()?;
+        Ok(TimeControl {periods, increment})
+    }
+}
+
+//Times are written as they were parsed, so fractions of a second such as "0.5+0.1" are kept.
+impl fmt::Display for TimeControl {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, period) in self.periods.iter().enumerate() {
+            if i > 0 {write!(f, ":")?;}
+            if let Some(moves) = period.moves {write!(f, "{}/", moves)?;}
+            write!(f, "{}", period.time.as_secs_f32())?;
+        }
+        match self.increment {
+            Increment::None => Ok(()),
+            Increment::Fischer(inc) => write!(f, "+{}", inc.as_secs_f32()),
+            Increment::Delay(delay) => write!(f, "+d{}", delay.as_secs_f32()),
+            Increment::Bronstein(delay) => write!(f, "+b{}", delay.as_secs_f32()),
+        }
+    }
+}
+
+#[derive(Component)]
+pub struct ClockText(pub PieceColor);
+
+fn index(color: PieceColor) -> usize {
+    if let PieceColor::White = color {0} else {1}
+}
+
+#[derive(Resource)]
+pub struct GameClock{
+    pub control: Option<TimeControl>,
+    //[white, black]
+    remaining: [Duration; 2],
+    period: [usize; 2],
+    moves_in_period: [u32; 2],
+    //Time spent on the current move, needed for the delay modes.
+    spent: Duration,
+    running: Option<PieceColor>,
+    //Number of plies the clock has seen, so it knows when a new move was made.
+    plies: usize,
+}
+
+impl GameClock {
+    pub fn new(control: Option<TimeControl>) -> Self {
+        let time = control.as_ref().map(|control| control.period(0).time).unwrap_or_default();
+        GameClock {
+            control,
+            remaining: [time; 2],
+            period: [0; 2],
+            moves_in_period: [0; 2],
+            spent: Duration::ZERO,
+            running: None,
+            plies: 0,
+        }
+    }
+
+    pub fn remaining(&self, color: PieceColor) -> Duration {
+        self.remaining[index(color)]
+    }
+
+    pub fn running(&self) -> Option<PieceColor> {
+        self.running
+    }
+
+    pub fn start(&mut self, color: PieceColor) {
+        if self.control.is_none() {return;}
+        self.running = Some(color);
+        self.spent = Duration::ZERO;
+    }
+
+    pub fn stop(&mut self) {
+        self.running = None;
+    }
+
+    //Count down the running clock, returning the color that just ran out of time.
+    pub fn tick(&mut self, delta: Duration) -> Option<PieceColor> {
+        let color = self.running?;
+        let control = self.control.as_ref()?;
+        let before = self.spent;
+        self.spent += delta;
+        let counted = match control.increment {
+            Increment::Delay(delay) => self.spent.saturating_sub(delay) - before.saturating_sub(delay),
+            _ => delta,
+        };
+        let remaining = &mut self.remaining[index(color)];
+        *remaining = remaining.saturating_sub(counted);
+        if remaining.is_zero() {
+            self.running = None;
+            return Some(color);
+        }
+        None
+    }
+
+    //Called once the given color has completed a move, which hands the clock to the opponent.
+    pub fn press(&mut self, color: PieceColor) {
+        let Some(control) = &self.control else {return};
+        let i = index(color);
+        match control.increment {
+            Increment::Fischer(inc) => self.remaining[i] += inc,
+            Increment::Bronstein(delay) => self.remaining[i] += delay.min(self.spent),
+            _ => (),
+        }
+        self.moves_in_period[i] += 1;
+        if Some(self.moves_in_period[i]) == control.period(self.period[i]).moves {
+            self.period[i] += 1;
+            self.moves_in_period[i] = 0;
+            self.remaining[i] += control.period(self.period[i]).time;
+        }
+        self.start(!color);
+    }
+}
+
+//Running out of time only loses if the opponent could still checkmate.
+pub fn timeout(board: &Board, flagged: PieceColor) -> GameOverState {
+    if board.can_checkmate(!flagged) {
+        GameOverState::Timeout(flagged)
+    } else {
+        GameOverState::TimeoutVsInsufficientMaterial(flagged)
+    }
+}
+
+//Text for a clock, showing tenths of a second once under ten seconds.
+pub fn format_clock(time: Duration) -> String {
+    let total = time.as_secs();
+    let (hours, minutes, seconds) = (total / 3600, (total / 60) % 60, total % 60);
+    if hours > 0 {
+        format!("{}:{:02}:{:02}", hours, minutes, seconds)
+    } else if total < 10 {
+        format!("{}:{:02}.{}", minutes, seconds, time.subsec_millis() / 100)
+    } else {
+        format!("{}:{:02}", minutes, seconds)
+    }
+}
+
+fn press_clock(
+    mut clock: ResMut<GameClock>,
+    board: Res<Board>,
+    mut er_board: EventReader<BoardChange>,
+) {
+    if er_board.read().last().is_none() {return;}
+    if board.movelist.len() > clock.plies {
+        if let Some(lastmove) = board.lastmove {
+            clock.press(lastmove.color);
+        }
+    } else if board.movelist.len() < clock.plies {
+        //A takeback hands the move, and the clock, back.
+        if clock.running().is_some() {clock.start(board.to_move());}
+    } else if clock.running().is_none() && clock.plies == 0 {
+        clock.start(board.to_move());
+    }
+    clock.plies = board.movelist.len();
+    if board.is_gameover() != GameOverState::Ongoing {
+        clock.stop();
+    }
+}
+
+fn tick_clock(
+    time: Res<Time>,
+    mut clock: ResMut<GameClock>,
+    mut board: ResMut<Board>,
+    mut ew_board: EventWriter<BoardChange>,
+) {
+    if let Some(flagged) = clock.tick(time.delta()) {
+        if board.outcome.is_some() {return;}
+        board.outcome = Some(timeout(&board, flagged));
+        ew_board.send(BoardChange);
+    }
+}
+
+fn update_clock_text(
+    clock: Res<GameClock>,
+    mut q_text: Query<(&mut Text, &ClockText)>,
+    settings: Res<ChessPluginSettings>,
+) {
+    if !clock.is_changed() && !settings.is_changed() {return;}
+    for (mut text, clock_text) in q_text.iter_mut() {
+        if clock.control.is_none() {
+            text.sections[0].value = String::new();
+            continue;
+        }
+        text.sections[0].value = format_clock(clock.remaining(clock_text.0));
+        text.sections[0].style.color = if clock.running() == Some(clock_text.0) {Color::WHITE} else {Color::GRAY};
+    }
+}
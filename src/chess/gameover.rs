@@ -0,0 +1,200 @@
+use bevy::prelude::*;
+use bevy_dragndrop::Draggable;
+
+use super::{ChessPluginSettings, NewGame, PieceEntity, piece_draggable};
+use super::setup::OpenSetup;
+use crate::core::board::{Board, GameOverState, PieceColor};
+use super::movelist::MoveListCache;
+use super::pgn::save_pgn;
+
+pub struct GameOverPlugin;
+
+impl Plugin for GameOverPlugin {
+    fn build(&self, app: &mut App) {
+        app
+        .add_systems(Update, (show_game_over, game_over_buttons, lock_pieces));
+    }
+}
+
+#[derive(Clone,Copy,PartialEq)]
+pub enum GameOverAction{
+    NewGame,
+    Rematch,
+    //Close the overlay to look back through the game.
+    Analyze,
+    SavePgn,
+}
+
+#[derive(Component)]
+pub struct GameOverOverlay;
+
+#[derive(Component)]
+pub struct GameOverButton(GameOverAction);
+
+//Line under the buttons saying where the game was saved.
+#[derive(Component)]
+pub struct GameOverStatus;
+
+//Headline and reason shown for a finished game, e.g. "White wins" "by checkmate".
+pub fn describe_outcome(outcome: GameOverState) -> (&'static str, &'static str) {
+    let title = match outcome.winner() {
+        Some(PieceColor::White) => "White wins",
+        Some(PieceColor::Black) => "Black wins",
+        None => "Draw",
+    };
+    let reason = match outcome {
+        GameOverState::Checkmate(_) => "by checkmate",
+        GameOverState::Stalemate => "by stalemate",
+        GameOverState::Resignation(_) => "by resignation",
+        GameOverState::AgreedDraw => "by agreement",
+        GameOverState::Timeout(_) => "on time",
+        GameOverState::TimeoutVsInsufficientMaterial(_) => "timeout vs insufficient material",
+        GameOverState::Repetition => "by threefold repetition",
+        GameOverState::FiftyMoves => "by the fifty-move rule",
+        GameOverState::InsufficientMaterial => "by insufficient material",
+        GameOverState::Adjudication(_) => "by adjudication",
+        GameOverState::KingOfTheHill(_) => "by king of the hill",
+        GameOverState::ThreeCheck(_) => "by three checks",
+        GameOverState::Forfeit(_) => "by forfeit",
+        GameOverState::Ongoing => "",
+    };
+    (title, reason)
+}
+
+fn show_game_over(
+    mut commands: Commands,
+    board: Res<Board>,
+    asset_server: Res<AssetServer>,
+    q_overlay: Query<Entity, With<GameOverOverlay>>,
+    //Whether the game was over when the board was last looked at, so the overlay only opens once.
+    mut was_over: Local<bool>,
+) {
+    if !board.is_changed() {return;}
+    let over = board.outcome.is_some();
+    if over == *was_over {return;}
+    *was_over = over;
+    for entity in q_overlay.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+    let Some(outcome) = board.outcome else {return};
+
+    let font = asset_server.load("fonts/FiraSans-Bold.ttf");
+    let text_style = |font_size| TextStyle {font: font.clone(), font_size, color: Color::WHITE};
+    let (title, reason) = describe_outcome(outcome);
+    commands.spawn((NodeBundle {
+        style: Style {
+            position_type: PositionType::Absolute,
+            width: Val::Percent(100.0),
+            height: Val::Percent(100.0),
+            justify_content: JustifyContent::Center,
+            align_items: AlignItems::Center,
+            ..default()
+        },
+        z_index: ZIndex::Global(10),
+        ..default()
+    }, GameOverOverlay))
+    .with_children(|parent| {
+        parent.spawn(NodeBundle {
+            style: Style {
+                flex_direction: FlexDirection::Column,
+                align_items: AlignItems::Center,
+                padding: UiRect::all(Val::Px(20.0)),
+                row_gap: Val::Px(10.0),
+                ..default()
+            },
+            background_color: Color::rgba(0.0, 0.0, 0.0, 0.75).into(),
+            ..default()
+        })
+        .with_children(|panel| {
+            panel.spawn(TextBundle::from_section(title, text_style(48.0)));
+            panel.spawn(TextBundle::from_section(reason, text_style(24.0)));
+            panel.spawn(NodeBundle {
+                style: Style {
+                    column_gap: Val::Px(10.0),
+                    margin: UiRect::top(Val::Px(10.0)),
+                    ..default()
+                },
+                ..default()
+            })
+            .with_children(|row| {
+                for (action, label) in [
+                    (GameOverAction::NewGame, "New Game"),
+                    (GameOverAction::Rematch, "Rematch"),
+                    (GameOverAction::Analyze, "Analyze"),
+                    (GameOverAction::SavePgn, "Save PGN"),
+                ] {
+                    row.spawn((ButtonBundle {
+                        style: Style {
+                            padding: UiRect::axes(Val::Px(12.0), Val::Px(6.0)),
+                            justify_content: JustifyContent::Center,
+                            align_items: AlignItems::Center,
+                            ..default()
+                        },
+                        background_color: Color::rgba(1.0, 1.0, 1.0, 0.15).into(),
+                        ..default()
+                    }, GameOverButton(action)))
+                    .with_children(|button| {
+                        button.spawn(TextBundle::from_section(label, text_style(20.0)));
+                    });
+                }
+            });
+            panel.spawn((TextBundle::from_section("", text_style(16.0)), GameOverStatus));
+        });
+    });
+}
+
+fn game_over_buttons(
+    mut commands: Commands,
+    mut q_buttons: Query<(&Interaction, &GameOverButton, &mut BackgroundColor), Changed<Interaction>>,
+    q_overlay: Query<Entity, With<GameOverOverlay>>,
+    mut q_status: Query<&mut Text, With<GameOverStatus>>,
+    board: Res<Board>,
+    mut cache: ResMut<MoveListCache>,
+    settings: Res<ChessPluginSettings>,
+    mut ew_new_game: EventWriter<NewGame>,
+    mut ew_setup: EventWriter<OpenSetup>,
+) {
+    for (interaction, button, mut color) in q_buttons.iter_mut() {
+        *color = match interaction {
+            Interaction::Pressed => Color::rgba(1.0, 1.0, 1.0, 0.35).into(),
+            Interaction::Hovered => Color::rgba(1.0, 1.0, 1.0, 0.25).into(),
+            Interaction::None => Color::rgba(1.0, 1.0, 1.0, 0.15).into(),
+        };
+        if *interaction != Interaction::Pressed {continue;}
+        match button.0 {
+            GameOverAction::NewGame => ew_setup.send(OpenSetup),
+            GameOverAction::Rematch => ew_new_game.send(NewGame {swap_colors: true}),
+            GameOverAction::Analyze => {
+                for entity in q_overlay.iter() {
+                    commands.entity(entity).despawn_recursive();
+                }
+            },
+            GameOverAction::SavePgn => {
+                cache.update(&board);
+                let status = match save_pgn(&board, &cache.san, &settings) {
+                    Ok(path) => format!("Saved to {}", path.display()),
+                    Err(e) => format!("Unable to save: {}", e),
+                };
+                for mut text in q_status.iter_mut() {
+                    text.sections[0].value = status.clone();
+                }
+            },
+        }
+    }
+}
+
+//Pieces can't be picked up once the game is over.
+fn lock_pieces(
+    mut commands: Commands,
+    board: Res<Board>,
+    q_pieces: Query<(Entity, Has<Draggable>), With<PieceEntity>>,
+) {
+    let over = board.outcome.is_some();
+    for (entity, draggable) in q_pieces.iter() {
+        if over && draggable {
+            commands.entity(entity).remove::<Draggable>();
+        } else if !over && !draggable {
+            commands.entity(entity).insert(piece_draggable());
+        }
+    }
+}
@@ -0,0 +1,65 @@
+pub const HINT_COLOR:Color = Color::rgba(0.0,0.0,0.0,0.2);
+
+use bevy::prelude::*;
+
+use bevy_dragndrop::*;
+use bevy_prototype_lyon::prelude::*;
+use bevy_prototype_lyon::shapes::Circle;
+
+use super::{BoardEntity, PieceEntity, TileEntity};
+use crate::core::board::Board;
+use crate::core::square::Square;
+
+//Marks a legal destination of the piece being dragged.
+#[derive(Component)]
+pub struct MoveHint;
+
+pub fn handle_hints_dragged(
+    commands: &mut Commands,
+    event: &Dragged,
+    board: &Board,
+    piece_ents: &Query<(&PieceEntity,&Parent)>,
+    tile_ents: &Query<&TileEntity>,
+    board_ent: &BoardEntity,
+) {
+    let Ok((_, parent)) = piece_ents.get(event.dragged) else {return};
+    let Ok(tile) = tile_ents.get(parent.get()) else {return};
+    let from = tile.square;
+    let mut destinations: Vec<Square> = board.legal_moves_from(from).iter().map(|bmove| bmove.to).collect();
+    //Promotions list the same square once per piece.
+    destinations.dedup();
+    for to in destinations {
+        let capture = board[to].is_some() || board.is_en_passant(from, to);
+        commands.entity(board_ent.tile(to)).with_children(|parent| {
+            //Lyon tessellates in path units, so draw at 100 units per tile and scale down.
+            let transform = Transform {
+                translation: Vec3::new(0.0,0.0,if capture {1.5} else {3.0}),
+                scale: Vec3::new(0.01,0.01,1.0),
+                ..default()
+            };
+            if capture {
+                parent.spawn((ShapeBundle {
+                    path: GeometryBuilder::build_as(&Circle {radius: 45.0, center: Vec2::ZERO}),
+                    spatial: SpatialBundle {transform, ..default()},
+                    ..default()
+                }, Stroke::new(HINT_COLOR, 8.0), MoveHint));
+            } else {
+                parent.spawn((ShapeBundle {
+                    path: GeometryBuilder::build_as(&Circle {radius: 15.0, center: Vec2::ZERO}),
+                    spatial: SpatialBundle {transform, ..default()},
+                    ..default()
+                }, Fill::color(HINT_COLOR), MoveHint));
+            }
+        });
+    }
+}
+
+pub fn clear_hints(
+    commands: &mut Commands,
+    q_hints: &Query<Entity, With<MoveHint>>,
+) {
+    for hint in q_hints.iter() {
+        commands.entity(hint).remove_parent();
+        commands.entity(hint).despawn();
+    }
+}
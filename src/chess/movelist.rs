@@ -0,0 +1,248 @@
+use bevy::prelude::*;
+use bevy::input::mouse::{MouseScrollUnit, MouseWheel};
+
+use super::{BoardChange, ChessPluginSettings};
+use crate::core::board::{Board, Move, PieceColor, SimpleMove};
+
+const PANEL_COLOR: Color = Color::rgba(0.0, 0.0, 0.0, 0.25);
+const CURRENT_COLOR: Color = Color::rgba(0.462745, 0.588235, 0.337254, 0.8);
+const LINE_HEIGHT: f32 = 20.0;
+
+pub struct MoveListPlugin;
+
+impl Plugin for MoveListPlugin {
+    fn build(&self, app: &mut App) {
+        app
+        .insert_resource(BoardView{ply: None})
+        .insert_resource(MoveListCache::default())
+        .add_systems(Startup, setup_move_list)
+        .add_systems(Update, (click_move, navigate_moves, update_move_list.after(click_move).after(navigate_moves), scroll_move_list));
+    }
+}
+
+//Which ply of the game is on screen. None follows the game as it is played.
+#[derive(Resource)]
+pub struct BoardView{
+    pub ply: Option<usize>,
+}
+
+impl BoardView {
+    pub fn is_live(&self) -> bool {
+        self.ply.is_none()
+    }
+
+    //Number of moves played in the displayed position.
+    pub fn shown_ply(&self, board: &Board) -> usize {
+        self.ply.unwrap_or(board.movelist.len()).min(board.movelist.len())
+    }
+
+    //Show the position after `ply` moves, returning whether the view changed.
+    pub fn go_to(&mut self, ply: usize, board: &Board) -> bool {
+        let ply = if ply >= board.movelist.len() {None} else {Some(ply)};
+        if ply == self.ply {return false;}
+        self.ply = ply;
+        true
+    }
+
+    pub fn position(&self, board: &Board) -> Board {
+        match self.ply {
+            Some(ply) if ply < board.movelist.len() => board.replay(ply),
+            _ => board.clone(),
+        }
+    }
+}
+
+//SAN for every move of the game, kept so that only new moves have to be replayed.
+#[derive(Resource, Default)]
+pub struct MoveListCache{
+    moves: Vec<Move>,
+    pub san: Vec<String>,
+}
+
+impl MoveListCache {
+    pub fn update(&mut self, board: &Board) {
+        let common = self.moves.iter().zip(board.movelist.iter()).take_while(|(a, b)| a == b).count();
+        self.moves.truncate(common);
+        self.san.truncate(common);
+        if common == board.movelist.len() {return;}
+        let mut position = board.replay(common);
+        for bmove in &board.movelist[common..] {
+            let simple = SimpleMove{from: bmove.from, to: bmove.to, promotion: bmove.promotion};
+            self.san.push(position.san(&simple));
+            position.make_simple_move(&simple);
+            self.moves.push(*bmove);
+        }
+    }
+}
+
+#[derive(Component)]
+pub struct MoveListContent{
+    position: f32,
+    //Keep the newest move in view until the player scrolls away from it.
+    follow: bool,
+}
+
+//A clickable move, holding the number of plies played once it is made.
+#[derive(Component)]
+pub struct MoveButton(usize);
+
+fn setup_move_list(mut commands: Commands, settings: Res<ChessPluginSettings>) {
+    let margins = settings.margins;
+    commands.spawn(NodeBundle {
+        style: Style {
+            position_type: PositionType::Absolute,
+            right: Val::Percent(1.0),
+            top: Val::Percent(margins.top),
+            width: Val::Percent(margins.right - 2.0),
+            height: Val::Percent(100.0 - (margins.top + margins.bottom)),
+            flex_direction: FlexDirection::Column,
+            overflow: Overflow::clip_y(),
+            ..default()
+        },
+        background_color: PANEL_COLOR.into(),
+        ..default()
+    })
+    .with_children(|parent| {
+        parent.spawn((NodeBundle {
+            style: Style {
+                flex_direction: FlexDirection::Column,
+                width: Val::Percent(100.0),
+                ..default()
+            },
+            ..default()
+        }, MoveListContent{position: 0.0, follow: true}));
+    });
+}
+
+fn update_move_list(
+    mut commands: Commands,
+    board: Res<Board>,
+    view: Res<BoardView>,
+    mut cache: ResMut<MoveListCache>,
+    mut er_board: EventReader<BoardChange>,
+    mut q_content: Query<(Entity, &mut MoveListContent)>,
+    asset_server: Res<AssetServer>,
+) {
+    if er_board.read().last().is_none() {return;}
+    cache.update(&board);
+    let Ok((content, mut list)) = q_content.get_single_mut() else {return};
+    if view.is_live() {list.follow = true;}
+    let current = view.shown_ply(&board);
+    let text_style = TextStyle {
+        font: asset_server.load("fonts/FiraMono-Medium.ttf"),
+        font_size: LINE_HEIGHT * 0.8,
+        color: Color::WHITE,
+    };
+    //A game that starts with Black to move leaves White's first cell empty.
+    let first = board.start_position();
+    let offset = usize::from(first.to_move() == PieceColor::Black);
+    let cells: Vec<Option<&String>> = std::iter::repeat_n(None, offset).chain(cache.san.iter().map(Some)).collect();
+    commands.entity(content).despawn_descendants();
+    commands.entity(content).with_children(|parent| {
+        for (number, pair) in cells.chunks(2).enumerate() {
+            parent.spawn(NodeBundle {
+                style: Style {
+                    flex_direction: FlexDirection::Row,
+                    height: Val::Px(LINE_HEIGHT),
+                    flex_shrink: 0.0,
+                    ..default()
+                },
+                ..default()
+            })
+            .with_children(|row| {
+                row.spawn(NodeBundle {
+                    style: Style {
+                        width: Val::Percent(20.0),
+                        justify_content: JustifyContent::FlexEnd,
+                        padding: UiRect::right(Val::Px(4.0)),
+                        ..default()
+                    },
+                    ..default()
+                })
+                .with_children(|cell| {
+                    cell.spawn(TextBundle::from_section(format!("{}.", first.fullmoves + number), TextStyle {color: Color::GRAY, ..text_style.clone()}));
+                });
+                for (i, san) in pair.iter().enumerate() {
+                    let Some(san) = san else {
+                        row.spawn(NodeBundle {style: Style {width: Val::Percent(40.0), ..default()}, ..default()});
+                        continue;
+                    };
+                    let ply = number * 2 + i + 1 - offset;
+                    row.spawn((ButtonBundle {
+                        style: Style {
+                            width: Val::Percent(40.0),
+                            padding: UiRect::left(Val::Px(4.0)),
+                            ..default()
+                        },
+                        background_color: if ply == current {CURRENT_COLOR.into()} else {Color::NONE.into()},
+                        ..default()
+                    }, MoveButton(ply)))
+                    .with_children(|button| {
+                        button.spawn(TextBundle::from_section(san.as_str(), text_style.clone()));
+                    });
+                }
+            });
+        }
+    });
+}
+
+fn click_move(
+    q_buttons: Query<(&Interaction, &MoveButton), Changed<Interaction>>,
+    board: Res<Board>,
+    mut view: ResMut<BoardView>,
+    mut ew_board: EventWriter<BoardChange>,
+) {
+    for (interaction, button) in q_buttons.iter() {
+        if *interaction == Interaction::Pressed && view.go_to(button.0, &board) {
+            ew_board.send(BoardChange);
+        }
+    }
+}
+
+fn navigate_moves(
+    keys: Res<Input<KeyCode>>,
+    board: Res<Board>,
+    mut view: ResMut<BoardView>,
+    mut ew_board: EventWriter<BoardChange>,
+) {
+    let current = view.shown_ply(&board);
+    let target = if keys.just_pressed(KeyCode::Left) {
+        current.saturating_sub(1)
+    } else if keys.just_pressed(KeyCode::Right) {
+        current + 1
+    } else if keys.just_pressed(KeyCode::Home) {
+        0
+    } else if keys.just_pressed(KeyCode::End) {
+        board.movelist.len()
+    } else {
+        return;
+    };
+    if view.go_to(target, &board) {
+        ew_board.send(BoardChange);
+    }
+}
+
+fn scroll_move_list(
+    mut er_wheel: EventReader<MouseWheel>,
+    mut q_content: Query<(&mut MoveListContent, &mut Style, &Parent, &Node)>,
+    q_node: Query<&Node>,
+) {
+    let scrolled: f32 = er_wheel.read().map(|event| match event.unit {
+        MouseScrollUnit::Line => event.y * LINE_HEIGHT,
+        MouseScrollUnit::Pixel => event.y,
+    }).sum();
+    for (mut list, mut style, parent, node) in q_content.iter_mut() {
+        let Ok(panel) = q_node.get(parent.get()) else {continue};
+        let max_scroll = (node.size().y - panel.size().y).max(0.0);
+        if scrolled != 0.0 {
+            list.position = (list.position + scrolled).clamp(-max_scroll, 0.0);
+            list.follow = list.position <= -max_scroll;
+        }
+        if list.follow {
+            list.position = -max_scroll;
+        }
+        if style.top != Val::Px(list.position) {
+            style.top = Val::Px(list.position);
+        }
+    }
+}
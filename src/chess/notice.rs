@@ -0,0 +1,103 @@
+use std::time::Duration;
+
+use bevy::prelude::*;
+
+use super::{ChessPluginSettings, EngineFailed, get_opposing_move};
+use crate::core::uci::EngineFallback;
+
+//How long a notice stays up.
+const NOTICE_DURATION: Duration = Duration::from_secs(6);
+
+pub struct NoticePlugin;
+
+impl Plugin for NoticePlugin {
+    fn build(&self, app: &mut App) {
+        app
+        .add_event::<Notice>()
+        .add_systems(Startup, setup_notice)
+        .add_systems(Update, (show_engine_failure.after(get_opposing_move), show_notice.after(show_engine_failure), hide_notice.after(show_notice)));
+    }
+}
+
+//Line under the board telling the player about something, such as the engine crashing or a setting changed by a key.
+#[derive(Component)]
+pub struct NoticeText{
+    timer: Timer,
+}
+
+//Text to show on the notice line.
+#[derive(Event)]
+pub struct Notice(pub String);
+
+fn setup_notice(
+    mut commands: Commands,
+    settings: Res<ChessPluginSettings>,
+    asset_server: Res<AssetServer>,
+) {
+    let margins = settings.margins;
+    commands.spawn(NodeBundle {
+        style: Style {
+            position_type: PositionType::Absolute,
+            left: Val::Percent(1.0),
+            top: Val::Percent(100.0 - margins.bottom + 1.0),
+            width: Val::Percent(100.0 - margins.right - 2.0),
+            height: Val::Percent(margins.bottom - 2.0),
+            justify_content: JustifyContent::Center,
+            align_items: AlignItems::Center,
+            ..default()
+        },
+        ..default()
+    })
+    .with_children(|parent| {
+        parent.spawn((TextBundle::from_section("", TextStyle {
+            font: asset_server.load("fonts/FiraSans-Bold.ttf"),
+            font_size: 20.0,
+            color: Color::rgb(1.0, 0.6, 0.5),
+        }), NoticeText{timer: Timer::new(NOTICE_DURATION, TimerMode::Once)}));
+    });
+}
+
+fn describe_fallback(fallback: EngineFallback) -> String {
+    match fallback {
+        EngineFallback::Forfeit => "The engine's side forfeits.".to_string(),
+        EngineFallback::Builtin{depth} => format!("The built-in engine played instead, at depth {}.", depth),
+        EngineFallback::Random => "A random move was played instead.".to_string(),
+    }
+}
+
+fn show_engine_failure(
+    mut er_failed: EventReader<EngineFailed>,
+    mut ew_notice: EventWriter<Notice>,
+) {
+    let Some(failure) = er_failed.read().last() else {return};
+    println!("Engine failed: {}", failure.error);
+    let mut message = failure.error.to_string();
+    //Messages are written in lower case to go after other text, but this one starts a sentence.
+    if let Some(first) = message.get_mut(..1) {
+        first.make_ascii_uppercase();
+    }
+    ew_notice.send(Notice(format!("{}. {}", message, describe_fallback(failure.fallback))));
+}
+
+fn show_notice(
+    mut er_notice: EventReader<Notice>,
+    mut q_notice: Query<(&mut Text, &mut NoticeText)>,
+) {
+    let Some(Notice(message)) = er_notice.read().last() else {return};
+    for (mut text, mut notice) in q_notice.iter_mut() {
+        text.sections[0].value = message.clone();
+        notice.timer.reset();
+    }
+}
+
+fn hide_notice(
+    time: Res<Time>,
+    mut q_notice: Query<(&mut Text, &mut NoticeText)>,
+) {
+    for (mut text, mut notice) in q_notice.iter_mut() {
+        if text.sections[0].value.is_empty() {continue;}
+        if notice.timer.tick(time.delta()).just_finished() {
+            text.sections[0].value.clear();
+        }
+    }
+}
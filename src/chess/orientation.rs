@@ -0,0 +1,84 @@
+use std::f32::consts::PI;
+
+use bevy::prelude::*;
+
+use super::BoardEntity;
+use crate::core::board::PieceColor;
+use crate::core::square::Rank;
+
+pub struct OrientationPlugin;
+
+impl Plugin for OrientationPlugin {
+    fn build(&self, app: &mut App) {
+        app
+        .add_systems(Update, (flip_board, orient_board.after(flip_board)));
+    }
+}
+
+//Which side of the board is drawn at the bottom of the screen. Starts with the player's color, but is not tied to it.
+#[derive(Resource)]
+pub struct BoardOrientation{
+    pub bottom: PieceColor,
+}
+
+impl BoardOrientation {
+    pub fn flipped(&self) -> bool {
+        self.bottom == PieceColor::Black
+    }
+
+    //Rank drawn in the top half of the screen.
+    pub fn is_top_half(&self, rank: Rank) -> bool {
+        (rank >= Rank::FIFTH) != self.flipped()
+    }
+
+    fn rotation(&self) -> Quat {
+        Quat::from_rotation_z(if self.flipped() {PI} else {0.})
+    }
+}
+
+//A child of the board that stays upright when the board is turned around.
+//Things around the board, like labels and clocks, also move to keep their place on screen.
+#[derive(Component)]
+pub struct Oriented{
+    white: Vec3,
+    black: Vec3,
+}
+
+impl Oriented {
+    //Translation in board space with White and with Black at the bottom.
+    pub fn new(white: Vec3, black: Vec3) -> Self {
+        Oriented {white, black}
+    }
+
+    //Stays where it is on the board, e.g. a tile.
+    pub fn fixed(translation: Vec3) -> Self {
+        Oriented {white: translation, black: translation}
+    }
+}
+
+fn flip_board(
+    keys: Res<Input<KeyCode>>,
+    mut orientation: ResMut<BoardOrientation>,
+) {
+    if keys.just_pressed(KeyCode::F) {
+        orientation.bottom = !orientation.bottom;
+    }
+}
+
+fn orient_board(
+    orientation: Res<BoardOrientation>,
+    mut q_board: Query<&mut Transform, With<BoardEntity>>,
+    mut q_oriented: Query<(Ref<Oriented>, &mut Transform), Without<BoardEntity>>,
+) {
+    let rotation = orientation.rotation();
+    if orientation.is_changed() {
+        for mut transform in q_board.iter_mut() {
+            transform.rotation = rotation;
+        }
+    }
+    for (oriented, mut transform) in q_oriented.iter_mut() {
+        if !orientation.is_changed() && !oriented.is_added() {continue;}
+        transform.translation = if orientation.flipped() {oriented.black} else {oriented.white};
+        transform.rotation = rotation;
+    }
+}
@@ -0,0 +1,19 @@
+use std::{fs, io, path::PathBuf, time::{SystemTime, UNIX_EPOCH}};
+
+use super::ChessPluginSettings;
+use crate::core::board::{Board, PieceColor};
+use crate::core::pgn::write_pgn;
+
+fn player_name(settings: &ChessPluginSettings, color: PieceColor) -> &'static str {
+    if settings.engine && color != settings.color {"Computer"} else {"Player"}
+}
+
+//Write the game to a new file in the working directory, returning where it went.
+pub fn save_pgn(board: &Board, san: &[String], settings: &ChessPluginSettings) -> io::Result<PathBuf> {
+    let seconds = SystemTime::now().duration_since(UNIX_EPOCH).map(|since| since.as_secs()).unwrap_or(0);
+    let path = PathBuf::from(format!("game-{}.pgn", seconds));
+    let time_control = settings.time_control.as_ref().map(ToString::to_string);
+    let players = [player_name(settings, PieceColor::White), player_name(settings, PieceColor::Black)];
+    fs::write(&path, write_pgn(board, san, players, time_control.as_deref()))?;
+    Ok(path)
+}
@@ -0,0 +1,257 @@
+use std::{fs, path::{Path, PathBuf}};
+
+use bevy::{
+    asset::io::file::FileAssetReader,
+    prelude::*,
+    render::render_resource::{Extent3d, TextureDimension, TextureFormat},
+    window::PrimaryWindow,
+};
+use resvg::{tiny_skia, usvg::{self, TreeParsing}};
+
+use super::{BoardEntity, ChessPluginSettings, PieceEntity, PieceTextureHolder, PieceTextures};
+use super::notice::Notice;
+use crate::core::board::{Piece, PieceColor, PieceType};
+
+//Every folder in here is a piece set, with a `white` and a `black` folder holding `king.svg`, `queen.png` and so on.
+pub const PIECE_SET_FOLDER: &str = "textures/pieces";
+
+pub struct PieceSetPlugin;
+
+impl Plugin for PieceSetPlugin {
+    fn build(&self, app: &mut App) {
+        app
+        .add_systems(Startup, setup_piece_sets)
+        .add_systems(Update, (switch_piece_set, apply_piece_set.after(switch_piece_set), rasterize_pieces, fit_piece_sprites.after(rasterize_pieces)));
+    }
+}
+
+//Where the image of a piece comes from.
+enum PieceSource{
+    //Loaded through the asset server, e.g. a png.
+    Image(String),
+    //Drawn at the size of a square, so it stays sharp however big the board is.
+    Svg(PathBuf),
+}
+
+pub struct PieceSet{
+    pub name: String,
+    sources: Vec<(Piece, PieceSource)>,
+}
+
+impl PieceSet {
+    //The set shipped with the game, for when the assets folder can not be searched.
+    fn classic() -> Self {
+        let sources = all_pieces().map(|piece| {
+            (piece, PieceSource::Image(format!("{}/classic/{}.png", PIECE_SET_FOLDER, piece_file(piece))))
+        }).collect();
+        PieceSet {name: "classic".to_string(), sources}
+    }
+
+    fn read(folder: &Path, name: &str) -> Option<Self> {
+        let mut sources = Vec::new();
+        for piece in all_pieces() {
+            let svg = folder.join(format!("{}.svg", piece_file(piece)));
+            let source = if svg.is_file() {
+                PieceSource::Svg(svg)
+            } else if folder.join(format!("{}.png", piece_file(piece))).is_file() {
+                PieceSource::Image(format!("{}/{}/{}.png", PIECE_SET_FOLDER, name, piece_file(piece)))
+            } else {
+                println!("Skipping piece set {}: no image for {}", name, piece_file(piece));
+                return None;
+            };
+            sources.push((piece, source));
+        }
+        Some(PieceSet {name: name.to_string(), sources})
+    }
+}
+
+fn all_pieces() -> impl Iterator<Item = Piece> {
+    [PieceColor::White, PieceColor::Black].into_iter().flat_map(|color| {
+        [PieceType::King, PieceType::Queen, PieceType::Rook, PieceType::Bishop, PieceType::Knight, PieceType::Pawn]
+            .into_iter()
+            .map(move |piece| Piece {piece, color})
+    })
+}
+
+//Path of a piece's image inside its set, without the extension.
+fn piece_file(piece: Piece) -> String {
+    let color = match piece.color {
+        PieceColor::White => "white",
+        PieceColor::Black => "black",
+    };
+    let name = match piece.piece {
+        PieceType::King => "king",
+        PieceType::Queen => "queen",
+        PieceType::Rook => "rook",
+        PieceType::Bishop => "bishop",
+        PieceType::Knight => "knight",
+        PieceType::Pawn => "pawn",
+    };
+    format!("{}/{}", color, name)
+}
+
+//Every complete set in the assets folder, sorted by name.
+fn find_piece_sets() -> Vec<PieceSet> {
+    let root = FileAssetReader::get_base_path().join("assets").join(PIECE_SET_FOLDER);
+    let Ok(entries) = fs::read_dir(&root) else {return Vec::new()};
+    let mut sets: Vec<PieceSet> = entries
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.path().is_dir())
+        .filter_map(|entry| PieceSet::read(&entry.path(), &entry.file_name().to_string_lossy()))
+        .collect();
+    sets.sort_by(|a, b| a.name.cmp(&b.name));
+    sets
+}
+
+//Draw an svg onto a square image, centered if it is not square itself.
+pub fn rasterize(path: &Path, size: u32) -> Image {
+    let mut pixmap = tiny_skia::Pixmap::new(size, size).unwrap();
+    let tree = fs::read(path)
+        .map_err(|e| e.to_string())
+        .and_then(|data| usvg::Tree::from_data(&data, &usvg::Options::default()).map_err(|e| e.to_string()));
+    match tree {
+        Ok(tree) => {
+            let tree = resvg::Tree::from_usvg(&tree);
+            let scale = size as f32 / tree.size.width().max(tree.size.height());
+            let x = (size as f32 - tree.size.width() * scale) / 2.0;
+            let y = (size as f32 - tree.size.height() * scale) / 2.0;
+            tree.render(tiny_skia::Transform::from_row(scale, 0.0, 0.0, scale, x, y), &mut pixmap.as_mut());
+        },
+        Err(e) => println!("Unable to draw {}: {}", path.display(), e),
+    }
+    //Bevy wants straight alpha, tiny-skia draws premultiplied.
+    let data = pixmap.pixels().iter().flat_map(|pixel| {
+        let color = pixel.demultiply();
+        [color.red(), color.green(), color.blue(), color.alpha()]
+    }).collect();
+    Image::new(
+        Extent3d {width: size, height: size, depth_or_array_layers: 1},
+        TextureDimension::D2,
+        data,
+        TextureFormat::Rgba8UnormSrgb,
+    )
+}
+
+//Every set that can be switched to, in the order the P key cycles through them.
+#[derive(Resource)]
+pub struct PieceSetLibrary{
+    pub sets: Vec<PieceSet>,
+    current: usize,
+    //Images of the current set's svg pieces, redrawn whenever the squares change size.
+    svg_images: Vec<(PathBuf, Handle<Image>)>,
+    //Width of a square in pixels.
+    resolution: u32,
+}
+
+impl PieceSetLibrary {
+    pub fn current(&self) -> &PieceSet {
+        &self.sets[self.current]
+    }
+
+    fn load(&mut self, index: usize, asset_server: &AssetServer, images: &mut Assets<Image>) -> PieceTextures {
+        self.current = index;
+        self.svg_images.clear();
+        let mut handles = Vec::new();
+        for (piece, source) in &self.sets[index].sources {
+            let handle = match source {
+                PieceSource::Image(path) => asset_server.load(path.clone()),
+                PieceSource::Svg(path) => {
+                    let handle = images.add(rasterize(path, self.resolution));
+                    self.svg_images.push((path.clone(), handle.clone()));
+                    handle
+                },
+            };
+            handles.push((*piece, handle));
+        }
+        PieceTextures::from_fn(|piece| {
+            handles.iter().find(|(known, _)| *known == piece).map(|(_, handle)| handle.clone()).unwrap()
+        })
+    }
+}
+
+fn setup_piece_sets(
+    mut commands: Commands,
+    settings: Res<ChessPluginSettings>,
+    mut texture_holder: ResMut<PieceTextureHolder>,
+    asset_server: Res<AssetServer>,
+    mut images: ResMut<Assets<Image>>,
+) {
+    let mut sets = find_piece_sets();
+    if sets.is_empty() {
+        sets.push(PieceSet::classic());
+    }
+    let current = sets.iter().position(|set| set.name == settings.piece_set).unwrap_or_else(|| {
+        println!("Piece set {} not found, using {}", settings.piece_set, sets[0].name);
+        0
+    });
+    //Drawn at the size of the png pieces until the board has been laid out.
+    let mut library = PieceSetLibrary {sets, current, svg_images: Vec::new(), resolution: 128};
+    texture_holder.textures = Some(library.load(current, &asset_server, &mut images));
+    commands.insert_resource(library);
+}
+
+fn switch_piece_set(
+    keys: Res<Input<KeyCode>>,
+    library: Option<ResMut<PieceSetLibrary>>,
+    mut texture_holder: ResMut<PieceTextureHolder>,
+    asset_server: Res<AssetServer>,
+    mut images: ResMut<Assets<Image>>,
+    mut ew_notice: EventWriter<Notice>,
+) {
+    let Some(mut library) = library else {return};
+    if !keys.just_pressed(KeyCode::P) || library.sets.len() < 2 {return;}
+    let next = (library.current + 1) % library.sets.len();
+    texture_holder.textures = Some(library.load(next, &asset_server, &mut images));
+    ew_notice.send(Notice(format!("Piece set: {}", library.sets[next].name)));
+}
+
+//Swap the images of the pieces already on the board.
+fn apply_piece_set(
+    texture_holder: Res<PieceTextureHolder>,
+    mut q_pieces: Query<(&PieceEntity, &mut Handle<Image>)>,
+) {
+    if !texture_holder.is_changed() {return;}
+    let Some(textures) = &texture_holder.textures else {return};
+    for (piece, mut texture) in q_pieces.iter_mut() {
+        *texture = textures.get_texture(piece.piece);
+    }
+}
+
+//Redraw svg pieces at the new square size when the board is resized.
+fn rasterize_pieces(
+    q_board: Query<&Transform, (With<BoardEntity>, Changed<Transform>)>,
+    q_windows: Query<&Window, With<PrimaryWindow>>,
+    library: Option<ResMut<PieceSetLibrary>>,
+    mut images: ResMut<Assets<Image>>,
+) {
+    let Some(mut library) = library else {return};
+    let (Ok(board), Ok(window)) = (q_board.get_single(), q_windows.get_single()) else {return};
+    let resolution = (board.scale.x / 8.0 * window.scale_factor() as f32).round().max(1.0) as u32;
+    if resolution == library.resolution {return;}
+    library.resolution = resolution;
+    for (path, handle) in &library.svg_images {
+        if let Some(image) = images.get_mut(handle) {
+            *image = rasterize(path, resolution);
+        }
+    }
+}
+
+//Sizes a piece by the square it stands on rather than by its image, so sets can use any resolution.
+#[derive(Component)]
+pub struct PieceSprite{
+    //Width in the parent's units.
+    pub size: f32,
+}
+
+fn fit_piece_sprites(
+    images: Res<Assets<Image>>,
+    mut q_sprites: Query<(&PieceSprite, &Handle<Image>, &mut Transform)>,
+) {
+    for (sprite, texture, mut transform) in q_sprites.iter_mut() {
+        let Some(image) = images.get(texture) else {continue};
+        let scale = sprite.size / image.size().max_element() as f32;
+        if transform.scale.x != scale {
+            transform.scale = Vec3::new(scale, scale, 1.0);
+        }
+    }
+}
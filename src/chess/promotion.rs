@@ -4,14 +4,17 @@ use bevy::window::PrimaryWindow;
 use super::{PieceTextureHolder,BoardEntity,BoardChange};
 
 use super::PIECE_SCALE;
-use super::board::{Piece, PieceColor, PieceType};
+use super::pieceset::PieceSprite;
+use crate::core::board::{Piece, PieceColor, PieceType};
+use crate::core::square::Square;
+use super::orientation::BoardOrientation;
 
 pub struct PromotionPlugin;
 
 impl Plugin for PromotionPlugin {
     fn build(&self, app: &mut App) {
         app
-        .add_systems(Update, (promotion_choice, promotion_choose))
+        .add_systems(Update, (promotion_choice, promotion_choose, update_promotion_textures))
         .add_event::<PromotionChoiceEvent>()
         .add_event::<PromotionChosenEvent>();
     }
@@ -23,22 +26,22 @@ pub struct PromotionBackground;
 #[derive(Component)]
 pub struct PromotionOption{
     piece: Piece,
-    from: (usize, usize),
-    to: (usize, usize)
+    from: Square,
+    to: Square
 }
 
 #[derive(Event)]
 pub struct PromotionChoiceEvent{
     pub color: PieceColor,
-    pub from: (usize, usize),
-    pub to: (usize, usize),
+    pub from: Square,
+    pub to: Square,
 }
 
 #[derive(Event)]
 pub struct PromotionChosenEvent{
     pub piece: Piece,
-    pub from: (usize, usize),
-    pub to: (usize, usize),
+    pub from: Square,
+    pub to: Square,
 }
 
 pub fn promotion_choice(
@@ -47,6 +50,7 @@ pub fn promotion_choice(
     texture_holder: Res<PieceTextureHolder>,
     board_ent: Query<(&BoardEntity, Entity)>,
     asset_server: Res<AssetServer>,
+    orientation: Res<BoardOrientation>,
 ) {
     if let Some(event) = er_promotion.read().last() {
         let (board_ent, board_entity) = board_ent.get_single().unwrap();
@@ -71,16 +75,18 @@ pub fn promotion_choice(
             .insert(PromotionBackground);
         });
         let circle: Handle<Image> = asset_server.load("textures/circle.png");
+        //Options stack from the promotion square towards the middle of the board.
+        let step = if orientation.is_top_half(event.to.rank()) {-1.0} else {1.0};
         for i in 0..4 {
             let piece = Piece{
                 piece: piece_types[i],
                 color: event.color,
             };
-            commands.entity(board_ent.tiles[event.to.0][event.to.1]).with_children(|parent| {
+            commands.entity(board_ent.tile(event.to)).with_children(|parent| {
                 parent.spawn(SpriteBundle{
                     transform: Transform {
                         scale: Vec3::new(PIECE_SCALE,PIECE_SCALE,1.0),
-                        translation: Vec3::new(0.0,0.0 - (i as f32),5.0),
+                        translation: Vec3::new(0.0,step * i as f32,5.0),
                         ..default()
                     },
                     texture: circle.clone(),
@@ -98,7 +104,7 @@ pub fn promotion_choice(
                         },
                         texture: textures.get_texture(piece),
                         ..default()
-                    });
+                    }).insert(PieceSprite{size: 128.0});
                 });
             });
         }
@@ -144,4 +150,19 @@ pub fn promotion_choose (
             }
         }
     }
-}
\ No newline at end of file
+}
+
+//Show the new piece set if it is switched while the picker is open.
+fn update_promotion_textures(
+    texture_holder: Res<PieceTextureHolder>,
+    q_option: Query<&PromotionOption>,
+    mut q_piece: Query<(&Parent, &mut Handle<Image>), With<PieceSprite>>,
+) {
+    if !texture_holder.is_changed() {return;}
+    let Some(textures) = &texture_holder.textures else {return};
+    for (parent, mut texture) in q_piece.iter_mut() {
+        if let Ok(option) = q_option.get(parent.get()) {
+            *texture = textures.get_texture(option.piece);
+        }
+    }
+}
@@ -0,0 +1,234 @@
+use bevy::{
+    prelude::*,
+    tasks::{block_on, AsyncComputeTaskPool, Task},
+};
+use futures_lite::future;
+
+use super::{BoardChange, ChessPluginSettings, EngineKind, updateboardstate};
+use super::buttons::{shade_margin_button, spawn_margin_buttons};
+use crate::core::board::{Board, GameOverState, PieceColor};
+use crate::core::uci::EngineSettings;
+
+pub struct ResignPlugin;
+
+impl Plugin for ResignPlugin {
+    fn build(&self, app: &mut App) {
+        app
+        .insert_resource(DrawOffer{pending: None, reply: OfferReply::None})
+        .add_event::<GameAction>()
+        .add_systems(Startup, setup_action_buttons)
+        .add_systems(Update, (
+            action_buttons,
+            handle_game_action.after(action_buttons).before(updateboardstate),
+            engine_draw_reply.before(updateboardstate),
+            lapse_draw_offer,
+            update_action_buttons.after(handle_game_action).after(engine_draw_reply).after(lapse_draw_offer),
+        ));
+    }
+}
+
+//How the engine answers a draw offer.
+#[derive(Clone,Copy,PartialEq,Debug)]
+pub enum DrawPolicy{
+    Always,
+    Never,
+    //Accept when the engine rates its own position at or below this many centipawns.
+    Evaluation(i32),
+}
+
+#[derive(Event,Clone,Copy,PartialEq)]
+pub enum GameAction{
+    Resign,
+    OfferDraw,
+    //Answers to an offer made by the other player at the same screen.
+    AcceptDraw,
+    DeclineDraw,
+}
+
+#[derive(Component)]
+pub struct GameActionButton(GameAction);
+
+#[derive(Clone,Copy,PartialEq)]
+pub enum OfferReply{
+    None,
+    //The engine is looking at the position.
+    Thinking,
+    Declined,
+}
+
+#[derive(Resource)]
+pub struct DrawOffer{
+    //Color that offered and the number of plies played at the time. Making a move instead of answering declines it.
+    pub pending: Option<(PieceColor, usize)>,
+    pub reply: OfferReply,
+}
+
+//The engine deciding whether to take a draw.
+#[derive(Component)]
+struct DrawReply(Task<bool>);
+
+//Color pressing the buttons: the player against the engine, whoever is to move otherwise.
+fn acting_color(board: &Board, settings: &ChessPluginSettings) -> PieceColor {
+    if settings.engine {settings.color} else {board.to_move()}
+}
+
+//Whether the engine playing engine_color takes a draw offered in this position.
+pub fn accepts_draw(board: &Board, engine_color: PieceColor, policy: DrawPolicy, kind: EngineKind, engine: &EngineSettings) -> bool {
+    match policy {
+        DrawPolicy::Always => true,
+        DrawPolicy::Never => false,
+        DrawPolicy::Evaluation(threshold) => {
+            //Asked of whichever engine is playing, so the built-in one doesn't need Stockfish to answer.
+            let evaluation = match kind {
+                EngineKind::Builtin{depth} => board.builtin_evaluation(depth),
+                EngineKind::Stockfish{..} => {
+                    let Some(evaluation) = board.engine_evaluation(engine) else {return false};
+                    evaluation
+                },
+            };
+            let evaluation = if board.to_move() == engine_color {evaluation} else {-evaluation};
+            evaluation <= threshold
+        },
+    }
+}
+
+fn setup_action_buttons(
+    mut commands: Commands,
+    settings: Res<ChessPluginSettings>,
+    asset_server: Res<AssetServer>,
+) {
+    spawn_margin_buttons(&mut commands, &asset_server, settings.margins, 1.0, settings.margins.top - 2.0, [
+        (GameActionButton(GameAction::Resign), "Resign"),
+        (GameActionButton(GameAction::OfferDraw), "Offer Draw"),
+        (GameActionButton(GameAction::AcceptDraw), "Accept Draw"),
+        (GameActionButton(GameAction::DeclineDraw), "Decline"),
+    ]);
+}
+
+fn action_buttons(
+    mut q_buttons: Query<(&Interaction, &GameActionButton, &mut BackgroundColor), Changed<Interaction>>,
+    mut ew_action: EventWriter<GameAction>,
+) {
+    for (interaction, button, mut color) in q_buttons.iter_mut() {
+        if shade_margin_button(*interaction, &mut color) {
+            ew_action.send(button.0);
+        }
+    }
+}
+
+fn handle_game_action(
+    mut commands: Commands,
+    mut er_action: EventReader<GameAction>,
+    mut board: ResMut<Board>,
+    mut offer: ResMut<DrawOffer>,
+    settings: Res<ChessPluginSettings>,
+    mut ew_board: EventWriter<BoardChange>,
+) {
+    for action in er_action.read() {
+        if board.outcome.is_some() {continue;}
+        let color = acting_color(&board, &settings);
+        match action {
+            GameAction::Resign => {
+                board.outcome = Some(GameOverState::Resignation(color));
+                ew_board.send(BoardChange);
+            },
+            GameAction::OfferDraw => {
+                if offer.pending.is_some() || offer.reply != OfferReply::None {continue;}
+                offer.pending = Some((color, board.movelist.len()));
+                if !settings.engine {continue;}
+                offer.reply = OfferReply::Thinking;
+                let board_clone = board.clone();
+                let (engine_color, policy, kind, engine) = (!settings.color, settings.draw_policy, settings.engine_kind, settings.engine_settings.clone());
+                let task = AsyncComputeTaskPool::get().spawn(async move {
+                    accepts_draw(&board_clone, engine_color, policy, kind, &engine)
+                });
+                commands.spawn(DrawReply(task));
+            },
+            GameAction::AcceptDraw => {
+                if offer.pending.is_none() {continue;}
+                offer.pending = None;
+                board.outcome = Some(GameOverState::AgreedDraw);
+                ew_board.send(BoardChange);
+            },
+            GameAction::DeclineDraw => {
+                offer.pending = None;
+            },
+        }
+    }
+}
+
+fn engine_draw_reply(
+    mut commands: Commands,
+    mut q_reply: Query<(Entity, &mut DrawReply)>,
+    mut board: ResMut<Board>,
+    mut offer: ResMut<DrawOffer>,
+    mut ew_board: EventWriter<BoardChange>,
+) {
+    for (entity, mut reply) in q_reply.iter_mut() {
+        let Some(accepted) = block_on(future::poll_once(&mut reply.0)) else {continue};
+        commands.entity(entity).despawn();
+        //An answer to an offer that has lapsed in the meantime counts for nothing.
+        if offer.pending.is_none() || board.outcome.is_some() {
+            offer.reply = OfferReply::None;
+            continue;
+        }
+        offer.pending = None;
+        if accepted {
+            offer.reply = OfferReply::None;
+            board.outcome = Some(GameOverState::AgreedDraw);
+            ew_board.send(BoardChange);
+        } else {
+            offer.reply = OfferReply::Declined;
+        }
+    }
+}
+
+//An offer stands until it is answered or a move is made, and a declined one can be made again after the next move.
+fn lapse_draw_offer(
+    board: Res<Board>,
+    mut offer: ResMut<DrawOffer>,
+    //Plies played the last time the board changed.
+    mut replied_at: Local<usize>,
+) {
+    if !board.is_changed() {return;}
+    let plies = board.movelist.len();
+    if board.outcome.is_some() || offer.pending.is_some_and(|(_, offered_at)| offered_at != plies) {
+        offer.pending = None;
+    }
+    if offer.reply == OfferReply::Declined && *replied_at != plies {
+        offer.reply = OfferReply::None;
+    }
+    *replied_at = plies;
+}
+
+//Show the offer buttons or the answer buttons, and hide them all once the game is over.
+fn update_action_buttons(
+    board: Res<Board>,
+    offer: Res<DrawOffer>,
+    settings: Res<ChessPluginSettings>,
+    mut q_buttons: Query<(&GameActionButton, &mut Style, &Children)>,
+    mut q_text: Query<&mut Text>,
+) {
+    if !board.is_changed() && !offer.is_changed() {return;}
+    //Without an engine both players share the screen, so an offer is answered straight away.
+    let answering = !settings.engine && offer.pending.is_some();
+    for (button, mut style, children) in q_buttons.iter_mut() {
+        let shown = board.outcome.is_none() && match button.0 {
+            GameAction::Resign | GameAction::OfferDraw => !answering,
+            GameAction::AcceptDraw | GameAction::DeclineDraw => answering,
+        };
+        style.display = if shown {Display::Flex} else {Display::None};
+        if button.0 != GameAction::OfferDraw {continue;}
+        let label = match offer.reply {
+            OfferReply::None if offer.pending.is_some() => "Draw Offered",
+            OfferReply::None => "Offer Draw",
+            OfferReply::Thinking => "Thinking...",
+            OfferReply::Declined => "Declined",
+        };
+        for child in children.iter() {
+            if let Ok(mut text) = q_text.get_mut(*child) {
+                text.sections[0].value = label.to_string();
+            }
+        }
+    }
+}
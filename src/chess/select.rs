@@ -0,0 +1,131 @@
+use bevy::prelude::*;
+use bevy::window::PrimaryWindow;
+
+use super::{BoardChange, BoardEntity, ChessPluginSettings, updateboardstate};
+use crate::core::board::Board;
+use crate::core::square::{File, Rank, Square};
+use super::movelist::BoardView;
+use super::promotion::{PromotionBackground, PromotionChoiceEvent};
+use super::theme::BoardTheme;
+
+pub struct SelectionPlugin;
+
+impl Plugin for SelectionPlugin {
+    fn build(&self, app: &mut App) {
+        app
+        .insert_resource(Selection{square: None, pressed: None, ply: 0})
+        .add_systems(Update, (click_to_move, draw_selection.after(click_to_move).after(updateboardstate)));
+    }
+}
+
+//Square picked by clicking, waiting for a click on its destination.
+#[derive(Resource)]
+pub struct Selection{
+    pub square: Option<Square>,
+    //Square the mouse went down on, so a click only counts if it is released there too.
+    pressed: Option<Square>,
+    //Number of moves played when the square was picked. A selection from an earlier position is stale.
+    ply: usize,
+}
+
+impl Selection {
+    pub fn selected(&self, board: &Board) -> Option<Square> {
+        if self.ply != board.movelist.len() {return None;}
+        self.square
+    }
+}
+
+#[derive(Component)]
+pub struct SelectionHighlight;
+
+//Square of the board under the cursor, if any.
+pub fn square_at_cursor(
+    window: &Window,
+    camera: (&Camera, &GlobalTransform),
+    board_transform: &GlobalTransform,
+) -> Option<Square> {
+    let world_position = window.cursor_position()
+        .and_then(|cursor| camera.0.viewport_to_world(camera.1, cursor))
+        .map(|ray| ray.origin)?;
+    let local = board_transform.affine().inverse().transform_point3(world_position);
+    if local.x.abs() >= 0.5 || local.y.abs() >= 0.5 {return None;}
+    Some(Square::new(File::new(((local.x + 0.5) * 8.0) as usize)?, Rank::new(((local.y + 0.5) * 8.0) as usize)?))
+}
+
+fn click_to_move(
+    buttons: Res<Input<MouseButton>>,
+    q_windows: Query<&Window, With<PrimaryWindow>>,
+    q_camera: Query<(&Camera, &GlobalTransform)>,
+    q_board: Query<&GlobalTransform, With<BoardEntity>>,
+    q_promotion: Query<(), With<PromotionBackground>>,
+    mut selection: ResMut<Selection>,
+    mut board: ResMut<Board>,
+    view: Res<BoardView>,
+    settings: Res<ChessPluginSettings>,
+    mut ew_promotion: EventWriter<PromotionChoiceEvent>,
+    mut ew_board: EventWriter<BoardChange>,
+) {
+    if !buttons.just_pressed(MouseButton::Left) && !buttons.just_released(MouseButton::Left) {return;}
+    let (Ok(window), Ok(camera), Ok(board_transform)) = (q_windows.get_single(), q_camera.get_single(), q_board.get_single()) else {return};
+    let square = square_at_cursor(window, camera, board_transform);
+    if buttons.just_pressed(MouseButton::Left) {
+        //Clicks on the promotion picker belong to it.
+        selection.pressed = if q_promotion.is_empty() {square} else {None};
+        return;
+    }
+    if selection.pressed.take() != square || !view.is_live() || board.outcome.is_some() {return;}
+    let Some(square) = square else {
+        selection.square = None;
+        return;
+    };
+
+    if let Some(from) = selection.selected(&board) {
+        selection.square = None;
+        if from == square {return;}
+        if board.is_legal_move(from, square) {
+            if board.can_promote(from, square) {
+                ew_promotion.send(PromotionChoiceEvent{color: board.to_move(), from, to: square});
+            } else {
+                board.make_move(from, square, None);
+                ew_board.send(BoardChange);
+            }
+            return;
+        }
+    }
+    //Picking up one of your own pieces selects it, anything else just deselects.
+    if board[square].is_some_and(|piece| settings.plays(piece.color)) {
+        selection.square = Some(square);
+        selection.ply = board.movelist.len();
+    }
+}
+
+fn draw_selection(
+    mut commands: Commands,
+    selection: Res<Selection>,
+    board: Res<Board>,
+    view: Res<BoardView>,
+    q_board: Query<Entity, With<BoardEntity>>,
+    q_highlight: Query<Entity, With<SelectionHighlight>>,
+    theme: Res<BoardTheme>,
+) {
+    if !selection.is_changed() && !board.is_changed() && !view.is_changed() && !theme.is_changed() {return;}
+    for entity in q_highlight.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+    let Some(square) = selection.selected(&board).filter(|_| view.is_live()) else {return};
+    let Ok(board_entity) = q_board.get_single() else {return};
+    commands.entity(board_entity).with_children(|parent| {
+        parent.spawn((SpriteBundle{
+            transform: Transform {
+                scale: Vec3::new(0.125,0.125,1.0),
+                translation: Vec3::new((square.file().index() as f32 * 0.125) - 0.4375, (square.rank().index() as f32 * 0.125) - 0.4375, 1.5),
+                ..default()
+            },
+            sprite: Sprite {
+                color: theme.selected,
+                ..default()
+            },
+            ..default()
+        }, SelectionHighlight));
+    });
+}
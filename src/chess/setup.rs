@@ -0,0 +1,400 @@
+use bevy::{
+    input::{keyboard::KeyboardInput, ButtonState},
+    prelude::*,
+    ui::UiSystem,
+};
+use rand::Rng;
+
+use super::{ChessPluginSettings, EngineKind, NewGame};
+use crate::core::board::{Board, PieceColor, Variant};
+
+pub struct SetupPlugin;
+
+impl Plugin for SetupPlugin {
+    fn build(&self, app: &mut App) {
+        app
+        .add_event::<OpenSetup>()
+        .add_systems(Startup, open_at_start)
+        .add_systems(PreUpdate, block_board_input.after(UiSystem::Focus).run_if(setup_open))
+        .add_systems(Update, (open_setup, setup_buttons.after(open_setup), type_fen.after(open_setup), update_setup.after(setup_buttons).after(type_fen)));
+    }
+}
+
+//Where a new game starts from.
+#[derive(Clone,PartialEq,Debug)]
+pub enum StartPosition{
+    Standard,
+    //A different random position every game.
+    Chess960,
+    Fen(String),
+}
+
+impl StartPosition {
+    pub fn board(&self, variant: Variant) -> Result<Board, String> {
+        let mut board = match self {
+            StartPosition::Standard => Board::new(),
+            StartPosition::Chess960 => Board::chess960(rand::thread_rng().gen_range(0..960)),
+            StartPosition::Fen(fen) => Board::from_fen(fen)?,
+        };
+        board.variant = variant;
+        Ok(board)
+    }
+}
+
+//Show the new game menu.
+#[derive(Event)]
+pub struct OpenSetup;
+
+#[derive(Clone,Copy,PartialEq,Debug)]
+enum SetupRow{
+    Side,
+    Opponent,
+    Level,
+    TimeControl,
+    Position,
+    Variant,
+}
+
+//Label and PGN time control of each clock on offer.
+const TIME_CONTROLS: [(&str, Option<&str>); 7] = [
+    ("None", None),
+    ("1+0", Some("60")),
+    ("3+2", Some("180+2")),
+    ("5+3", Some("300+3")),
+    ("10+5", Some("600+5")),
+    ("15+10", Some("900+10")),
+    ("30+0", Some("1800")),
+];
+
+const VARIANTS: [Variant; 3] = [Variant::Standard, Variant::KingOfTheHill, Variant::ThreeCheck];
+
+impl SetupRow {
+    const ALL: [SetupRow; 6] = [SetupRow::Side, SetupRow::Opponent, SetupRow::Level, SetupRow::TimeControl, SetupRow::Position, SetupRow::Variant];
+
+    fn title(&self) -> &'static str {
+        match self {
+            SetupRow::Side => "Play as",
+            SetupRow::Opponent => "Opponent",
+            SetupRow::Level => "Level",
+            SetupRow::TimeControl => "Clock",
+            SetupRow::Position => "Start from",
+            SetupRow::Variant => "Variant",
+        }
+    }
+
+    fn options(&self) -> Vec<&'static str> {
+        match self {
+            SetupRow::Side => vec!["White", "Black", "Random"],
+            SetupRow::Opponent => vec!["Human", "Stockfish", "Built-in"],
+            SetupRow::Level => vec!["1", "2", "3", "4", "5", "6", "7", "8"],
+            SetupRow::TimeControl => TIME_CONTROLS.iter().map(|(label, _)| *label).collect(),
+            SetupRow::Position => vec!["Standard", "Chess960", "FEN"],
+            SetupRow::Variant => vec!["Standard", "King of the Hill", "Three-check"],
+        }
+    }
+}
+
+//What the menu has picked, kept from one game to the next.
+#[derive(Resource)]
+pub struct SetupChoices{
+    //Index of the option picked in each row, in the order of SetupRow::ALL.
+    chosen: [usize; 6],
+    pub fen: String,
+}
+
+impl SetupChoices {
+    fn from_settings(settings: &ChessPluginSettings) -> Self {
+        let opponent = match (settings.engine, settings.engine_kind) {
+            (false, _) => 0,
+            (true, EngineKind::Stockfish{..}) => 1,
+            (true, EngineKind::Builtin{..}) => 2,
+        };
+        let level = match settings.engine_kind {
+            EngineKind::Stockfish{skill} => (skill.min(20) as usize * 7 + 10) / 20,
+            EngineKind::Builtin{depth} => (depth.max(1) as usize - 1) * 3,
+        }.min(7);
+        let time_control = settings.time_control.as_ref().map(ToString::to_string);
+        let time_control = TIME_CONTROLS.iter().position(|(_, control)| control.map(str::to_string) == time_control).unwrap_or(0);
+        let (position, fen) = match &settings.start_position {
+            StartPosition::Standard => (0, Board::new().fen()),
+            StartPosition::Chess960 => (1, Board::new().fen()),
+            StartPosition::Fen(fen) => (2, fen.clone()),
+        };
+        let variant = VARIANTS.iter().position(|variant| *variant == settings.variant).unwrap_or(0);
+        //The side is random until the player picks one.
+        SetupChoices {chosen: [2, opponent, level, time_control, position, variant], fen}
+    }
+
+    fn get(&self, row: SetupRow) -> usize {
+        self.chosen[row as usize]
+    }
+
+    //Write the choices into the settings, failing if they don't make a game.
+    fn apply(&self, settings: &mut ChessPluginSettings) -> Result<(), String> {
+        let variant = VARIANTS[self.get(SetupRow::Variant)];
+        let start_position = match self.get(SetupRow::Position) {
+            0 => StartPosition::Standard,
+            1 => StartPosition::Chess960,
+            _ => StartPosition::Fen(self.fen.trim().to_string()),
+        };
+        start_position.board(variant).map_err(|e| format!("Invalid FEN: {}", e))?;
+        settings.start_position = start_position;
+        settings.variant = variant;
+        settings.color = match self.get(SetupRow::Side) {
+            0 => PieceColor::White,
+            1 => PieceColor::Black,
+            _ => if rand::thread_rng().gen_bool(0.5) {PieceColor::White} else {PieceColor::Black},
+        };
+        let level = self.get(SetupRow::Level);
+        settings.engine = self.get(SetupRow::Opponent) != 0;
+        settings.engine_kind = if self.get(SetupRow::Opponent) == 2 {
+            EngineKind::Builtin{depth: 1 + level as u32 / 3}
+        } else {
+            EngineKind::Stockfish{skill: ((level * 20 + 3) / 7) as u8}
+        };
+        settings.time_control = TIME_CONTROLS[self.get(SetupRow::TimeControl)].1.and_then(|control| control.parse().ok());
+        Ok(())
+    }
+}
+
+#[derive(Component)]
+pub struct SetupMenu;
+
+#[derive(Component)]
+struct SetupRowNode(SetupRow);
+
+#[derive(Component)]
+struct SetupOption{
+    row: SetupRow,
+    index: usize,
+}
+
+#[derive(Component,Clone,Copy,PartialEq)]
+enum SetupAction{
+    Start,
+    //Close the menu and carry on with the game behind it.
+    Back,
+}
+
+//The line the FEN is typed into.
+#[derive(Component)]
+struct SetupFen;
+
+#[derive(Component)]
+struct SetupError;
+
+pub fn setup_open(q_menu: Query<(), With<SetupMenu>>) -> bool {
+    !q_menu.is_empty()
+}
+
+fn open_at_start(
+    mut commands: Commands,
+    settings: Res<ChessPluginSettings>,
+    mut ew_open: EventWriter<OpenSetup>,
+) {
+    commands.insert_resource(SetupChoices::from_settings(&settings));
+    if settings.setup_menu {
+        ew_open.send(OpenSetup);
+    }
+}
+
+//While the menu is up the keyboard types into it and clicks belong to it, not to the board behind it.
+fn block_board_input(
+    mut keys: ResMut<Input<KeyCode>>,
+    mut buttons: ResMut<Input<MouseButton>>,
+) {
+    keys.reset_all();
+    buttons.reset_all();
+}
+
+fn button_color(selected: bool, interaction: Interaction) -> BackgroundColor {
+    let alpha = match (selected, interaction) {
+        (true, _) | (_, Interaction::Pressed) => 0.45,
+        (false, Interaction::Hovered) => 0.25,
+        (false, Interaction::None) => 0.15,
+    };
+    Color::rgba(1.0, 1.0, 1.0, alpha).into()
+}
+
+fn spawn_button(parent: &mut ChildBuilder, label: &str, text_style: TextStyle, bundle: impl Bundle) {
+    parent.spawn((ButtonBundle {
+        style: Style {
+            padding: UiRect::axes(Val::Px(10.0), Val::Px(4.0)),
+            justify_content: JustifyContent::Center,
+            align_items: AlignItems::Center,
+            ..default()
+        },
+        background_color: button_color(false, Interaction::None),
+        ..default()
+    }, bundle))
+    .with_children(|button| {
+        button.spawn(TextBundle::from_section(label, text_style));
+    });
+}
+
+fn open_setup(
+    mut commands: Commands,
+    mut er_open: EventReader<OpenSetup>,
+    asset_server: Res<AssetServer>,
+    choices: Res<SetupChoices>,
+    q_menu: Query<(), With<SetupMenu>>,
+) {
+    if er_open.read().last().is_none() || !q_menu.is_empty() {return;}
+    let font = asset_server.load("fonts/FiraSans-Bold.ttf");
+    let text_style = |font_size| TextStyle {font: font.clone(), font_size, color: Color::WHITE};
+    commands.spawn((NodeBundle {
+        style: Style {
+            position_type: PositionType::Absolute,
+            width: Val::Percent(100.0),
+            height: Val::Percent(100.0),
+            justify_content: JustifyContent::Center,
+            align_items: AlignItems::Center,
+            ..default()
+        },
+        background_color: Color::rgba(0.0, 0.0, 0.0, 0.5).into(),
+        //Above the game over overlay, which it is opened from.
+        z_index: ZIndex::Global(20),
+        ..default()
+    }, SetupMenu))
+    .with_children(|parent| {
+        parent.spawn(NodeBundle {
+            style: Style {
+                flex_direction: FlexDirection::Column,
+                align_items: AlignItems::Center,
+                padding: UiRect::all(Val::Px(20.0)),
+                row_gap: Val::Px(10.0),
+                ..default()
+            },
+            background_color: Color::rgba(0.0, 0.0, 0.0, 0.85).into(),
+            ..default()
+        })
+        .with_children(|panel| {
+            panel.spawn(TextBundle::from_section("New Game", text_style(40.0)));
+            for row in SetupRow::ALL {
+                panel.spawn((NodeBundle {
+                    style: Style {
+                        width: Val::Percent(100.0),
+                        align_items: AlignItems::Center,
+                        column_gap: Val::Px(6.0),
+                        ..default()
+                    },
+                    ..default()
+                }, SetupRowNode(row)))
+                .with_children(|line| {
+                    line.spawn(TextBundle::from_section(row.title(), text_style(20.0)).with_style(Style {
+                        width: Val::Px(110.0),
+                        ..default()
+                    }));
+                    for (index, label) in row.options().into_iter().enumerate() {
+                        spawn_button(line, label, text_style(18.0), SetupOption{row, index});
+                    }
+                });
+            }
+            panel.spawn((TextBundle::from_section(choices.fen.clone(), TextStyle {
+                font: asset_server.load("fonts/FiraMono-Medium.ttf"),
+                font_size: 16.0,
+                color: Color::WHITE,
+            }).with_background_color(Color::rgba(1.0, 1.0, 1.0, 0.1)), SetupFen));
+            panel.spawn((TextBundle::from_section("", TextStyle {color: Color::rgb(1.0, 0.5, 0.5), ..text_style(16.0)}), SetupError));
+            panel.spawn(NodeBundle {
+                style: Style {
+                    column_gap: Val::Px(10.0),
+                    ..default()
+                },
+                ..default()
+            })
+            .with_children(|row| {
+                spawn_button(row, "Start", text_style(24.0), SetupAction::Start);
+                spawn_button(row, "Back", text_style(24.0), SetupAction::Back);
+            });
+        });
+    });
+}
+
+fn setup_buttons(
+    mut commands: Commands,
+    mut q_options: Query<(Ref<Interaction>, &SetupOption, &mut BackgroundColor)>,
+    mut q_actions: Query<(&Interaction, &SetupAction, &mut BackgroundColor), (Changed<Interaction>, Without<SetupOption>)>,
+    q_menu: Query<Entity, With<SetupMenu>>,
+    mut q_error: Query<&mut Text, With<SetupError>>,
+    mut choices: ResMut<SetupChoices>,
+    mut settings: ResMut<ChessPluginSettings>,
+    mut ew_new_game: EventWriter<NewGame>,
+) {
+    for (interaction, option, _) in q_options.iter() {
+        if interaction.is_changed() && *interaction == Interaction::Pressed && choices.get(option.row) != option.index {
+            choices.chosen[option.row as usize] = option.index;
+        }
+    }
+    for (interaction, option, mut color) in q_options.iter_mut() {
+        if !interaction.is_changed() && !choices.is_changed() {continue;}
+        *color = button_color(choices.get(option.row) == option.index, *interaction);
+    }
+    for (interaction, action, mut color) in q_actions.iter_mut() {
+        *color = button_color(false, *interaction);
+        if *interaction != Interaction::Pressed {continue;}
+        if *action == SetupAction::Start {
+            if let Err(e) = choices.apply(&mut settings) {
+                for mut text in q_error.iter_mut() {
+                    text.sections[0].value = e.clone();
+                }
+                continue;
+            }
+            ew_new_game.send(NewGame {swap_colors: false});
+        }
+        for entity in q_menu.iter() {
+            commands.entity(entity).despawn_recursive();
+        }
+    }
+}
+
+fn type_fen(
+    mut er_chars: EventReader<ReceivedCharacter>,
+    mut er_keys: EventReader<KeyboardInput>,
+    mut choices: ResMut<SetupChoices>,
+    q_menu: Query<(), With<SetupMenu>>,
+) {
+    if q_menu.is_empty() || choices.get(SetupRow::Position) != 2 {
+        er_chars.clear();
+        er_keys.clear();
+        return;
+    }
+    for event in er_chars.read() {
+        if !event.char.is_control() {
+            choices.fen.push(event.char);
+        }
+    }
+    for event in er_keys.read() {
+        if event.state != ButtonState::Pressed {continue;}
+        match event.key_code {
+            Some(KeyCode::Back) => {choices.fen.pop();},
+            //Start over instead of deleting the old FEN a character at a time.
+            Some(KeyCode::Delete) => choices.fen.clear(),
+            _ => (),
+        }
+    }
+}
+
+//Only show the level with an engine to play, and the FEN when starting from one.
+fn update_setup(
+    choices: Res<SetupChoices>,
+    mut q_rows: Query<(&SetupRowNode, &mut Style)>,
+    mut q_fen: Query<(&mut Text, &mut Style), (With<SetupFen>, Without<SetupRowNode>)>,
+    mut q_error: Query<&mut Text, (With<SetupError>, Without<SetupFen>)>,
+    q_added: Query<(), Added<SetupMenu>>,
+) {
+    if !choices.is_changed() && q_added.is_empty() {return;}
+    let display = |shown: bool| if shown {Display::Flex} else {Display::None};
+    for (row, mut style) in q_rows.iter_mut() {
+        if row.0 == SetupRow::Level {
+            style.display = display(choices.get(SetupRow::Opponent) != 0);
+        }
+    }
+    for (mut text, mut style) in q_fen.iter_mut() {
+        style.display = display(choices.get(SetupRow::Position) == 2);
+        text.sections[0].value = format!("{}_", choices.fen);
+    }
+    for mut text in q_error.iter_mut() {
+        text.sections[0].value.clear();
+    }
+}
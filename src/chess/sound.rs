@@ -0,0 +1,288 @@
+use std::{f32::consts::TAU, time::Duration};
+
+use bevy::{
+    asset::io::file::FileAssetReader,
+    audio::{AddAudioSource, Source, Volume},
+    prelude::*,
+};
+
+use super::{ChessPluginSettings, IllegalMove};
+use crate::core::board::{Board, GameOverState, PieceColor, PieceType};
+use super::clock::GameClock;
+use super::notice::Notice;
+
+//Every folder in here is a sound pack, holding `move.ogg`, `capture.ogg` and so on.
+pub const SOUND_FOLDER: &str = "sounds";
+//The player's clock warns once when it drops below this.
+pub const LOW_TIME: Duration = Duration::from_secs(10);
+const SAMPLE_RATE: u32 = 44100;
+
+pub struct SoundPlugin;
+
+impl Plugin for SoundPlugin {
+    fn build(&self, app: &mut App) {
+        app
+        .add_audio_source::<Chime>()
+        .add_event::<PlaySound>()
+        .add_systems(Startup, setup_sounds)
+        .add_systems(Update, (board_sounds, low_time_sound, change_volume, play_sounds.after(board_sounds).after(low_time_sound)));
+    }
+}
+
+#[derive(Clone)]
+pub struct SoundSettings{
+    //Name of the folder in assets/sounds. Sounds missing from it, or all of them for "default", are the built-in ones.
+    pub pack: String,
+    //From 0 (silent) to 1.
+    pub volume: f32,
+}
+
+#[derive(Clone,Copy,PartialEq,Debug)]
+pub enum Sound{
+    Move,
+    Capture,
+    Castle,
+    Check,
+    Promotion,
+    Illegal,
+    LowTime,
+    GameEnd,
+}
+
+impl Sound {
+    const ALL: [Sound; 8] = [Sound::Move, Sound::Capture, Sound::Castle, Sound::Check, Sound::Promotion, Sound::Illegal, Sound::LowTime, Sound::GameEnd];
+
+    fn file_name(&self) -> &'static str {
+        match self {
+            Sound::Move => "move",
+            Sound::Capture => "capture",
+            Sound::Castle => "castle",
+            Sound::Check => "check",
+            Sound::Promotion => "promotion",
+            Sound::Illegal => "illegal",
+            Sound::LowTime => "low_time",
+            Sound::GameEnd => "game_end",
+        }
+    }
+
+    //Notes as (frequency, start, length), in Hz and seconds.
+    fn chime(&self) -> Chime {
+        let notes: &[(f32, f32, f32)] = match self {
+            Sound::Move => &[(330.0, 0.0, 0.08), (660.0, 0.0, 0.04)],
+            Sound::Capture => &[(196.0, 0.0, 0.14), (392.0, 0.0, 0.07), (247.0, 0.03, 0.1)],
+            Sound::Castle => &[(330.0, 0.0, 0.08), (660.0, 0.0, 0.04), (294.0, 0.1, 0.08), (588.0, 0.1, 0.04)],
+            Sound::Check => &[(880.0, 0.0, 0.25), (1320.0, 0.0, 0.15)],
+            Sound::Promotion => &[(523.0, 0.0, 0.15), (659.0, 0.08, 0.15), (784.0, 0.16, 0.3)],
+            Sound::Illegal => &[(110.0, 0.0, 0.15), (104.0, 0.0, 0.15)],
+            Sound::LowTime => &[(988.0, 0.0, 0.1), (988.0, 0.15, 0.1)],
+            Sound::GameEnd => &[(523.0, 0.0, 0.6), (659.0, 0.0, 0.6), (784.0, 0.0, 0.8)],
+        };
+        Chime {notes: notes.iter().map(|&(frequency, start, length)| Note {frequency, start, length}).collect()}
+    }
+}
+
+//Play a sound from the current pack.
+#[derive(Event)]
+pub struct PlaySound(pub Sound);
+
+#[derive(Clone,Copy)]
+struct Note{
+    frequency: f32,
+    start: f32,
+    length: f32,
+}
+
+//A sound made of decaying sine waves, so there is something to hear without any sound files.
+#[derive(Asset,TypePath,Clone)]
+pub struct Chime{
+    notes: Vec<Note>,
+}
+
+pub struct ChimeDecoder{
+    notes: Vec<Note>,
+    sample: u32,
+    samples: u32,
+}
+
+impl Iterator for ChimeDecoder {
+    type Item = f32;
+
+    fn next(&mut self) -> Option<f32> {
+        if self.sample >= self.samples {return None;}
+        let time = self.sample as f32 / SAMPLE_RATE as f32;
+        self.sample += 1;
+        Some(self.notes.iter()
+            .filter(|note| time >= note.start && time < note.start + note.length)
+            .map(|note| {
+                let time = time - note.start;
+                //Fade in over a few milliseconds so it doesn't click, then die away like a struck bell.
+                let envelope = (time / 0.005).min(1.0) * (1.0 - time / note.length).powi(2);
+                (time * note.frequency * TAU).sin() * envelope * 0.3
+            })
+            .sum())
+    }
+}
+
+impl Source for ChimeDecoder {
+    fn current_frame_len(&self) -> Option<usize> {
+        Some((self.samples - self.sample) as usize)
+    }
+
+    fn channels(&self) -> u16 {
+        1
+    }
+
+    fn sample_rate(&self) -> u32 {
+        SAMPLE_RATE
+    }
+
+    fn total_duration(&self) -> Option<Duration> {
+        Some(Duration::from_secs_f32(self.samples as f32 / SAMPLE_RATE as f32))
+    }
+}
+
+impl Decodable for Chime {
+    type DecoderItem = f32;
+    type Decoder = ChimeDecoder;
+
+    fn decoder(&self) -> ChimeDecoder {
+        let length = self.notes.iter().map(|note| note.start + note.length).fold(0.0, f32::max);
+        ChimeDecoder {notes: self.notes.clone(), sample: 0, samples: (length * SAMPLE_RATE as f32) as u32}
+    }
+}
+
+enum SoundSource{
+    File(Handle<AudioSource>),
+    Chime(Handle<Chime>),
+}
+
+#[derive(Resource)]
+pub struct SoundPack{
+    sounds: Vec<(Sound, SoundSource)>,
+    pub volume: f32,
+    pub muted: bool,
+}
+
+fn setup_sounds(
+    mut commands: Commands,
+    settings: Res<ChessPluginSettings>,
+    asset_server: Res<AssetServer>,
+    mut chimes: ResMut<Assets<Chime>>,
+) {
+    let Some(sound_settings) = &settings.sounds else {return};
+    let folder = FileAssetReader::get_base_path().join("assets").join(SOUND_FOLDER).join(&sound_settings.pack);
+    if sound_settings.pack != "default" && !folder.is_dir() {
+        println!("Sound pack {} not found, using the built-in sounds", sound_settings.pack);
+    }
+    let sounds = Sound::ALL.iter().map(|sound| {
+        let file = format!("{}.ogg", sound.file_name());
+        let source = if folder.join(&file).is_file() {
+            SoundSource::File(asset_server.load(format!("{}/{}/{}", SOUND_FOLDER, sound_settings.pack, file)))
+        } else {
+            SoundSource::Chime(chimes.add(sound.chime()))
+        };
+        (*sound, source)
+    }).collect();
+    commands.insert_resource(SoundPack {sounds, volume: sound_settings.volume.clamp(0.0, 1.0), muted: false});
+}
+
+//The sound for the move that was just played.
+fn move_sound(board: &Board) -> Sound {
+    let Some(bmove) = board.movelist.last() else {return Sound::Move};
+    if board.is_check(board.to_move()) {
+        Sound::Check
+    } else if bmove.promotion.is_some() {
+        Sound::Promotion
+    } else if bmove.piece.piece == PieceType::King && board.replay(board.movelist.len() - 1).can_castle(bmove.from, bmove.to).cancastle {
+        //Asked of the position before the move, as a Chess960 king may castle by a single file or onto its own rook.
+        Sound::Castle
+    } else if bmove.capture {
+        Sound::Capture
+    } else {
+        Sound::Move
+    }
+}
+
+fn board_sounds(
+    board: Res<Board>,
+    mut er_illegal: EventReader<IllegalMove>,
+    mut ew_sound: EventWriter<PlaySound>,
+    //Plies played and whether the game was over when the board was last looked at.
+    mut seen: Local<(usize, bool)>,
+) {
+    if er_illegal.read().last().is_some() {
+        ew_sound.send(PlaySound(Sound::Illegal));
+    }
+    if !board.is_changed() {return;}
+    let plies = board.movelist.len();
+    let over = board.is_gameover() != GameOverState::Ongoing;
+    if over && !seen.1 {
+        ew_sound.send(PlaySound(Sound::GameEnd));
+    } else if plies > seen.0 {
+        ew_sound.send(PlaySound(move_sound(&board)));
+    }
+    //Takebacks only make the game shorter, which is silent.
+    *seen = (plies, over);
+}
+
+fn low_time_sound(
+    clock: Res<GameClock>,
+    settings: Res<ChessPluginSettings>,
+    mut ew_sound: EventWriter<PlaySound>,
+    //[white, black]
+    mut warned: Local<[bool; 2]>,
+) {
+    let Some(color) = clock.running() else {return};
+    //Only warn about the engine's clock when nobody is playing it.
+    if settings.engine && color != settings.color {return;}
+    let warned = &mut warned[if let PieceColor::White = color {0} else {1}];
+    let low = clock.remaining(color) < LOW_TIME;
+    if low && !*warned {
+        ew_sound.send(PlaySound(Sound::LowTime));
+    }
+    //An increment can lift the clock back over the line, so it can warn again.
+    *warned = low;
+}
+
+fn change_volume(
+    keys: Res<Input<KeyCode>>,
+    pack: Option<ResMut<SoundPack>>,
+    mut ew_notice: EventWriter<Notice>,
+) {
+    let Some(mut pack) = pack else {return};
+    if keys.just_pressed(KeyCode::M) {
+        pack.muted = !pack.muted;
+        ew_notice.send(Notice(format!("Sound {}", if pack.muted {"muted"} else {"unmuted"})));
+        return;
+    }
+    let step = if keys.any_just_pressed([KeyCode::Equals, KeyCode::NumpadAdd]) {
+        0.1
+    } else if keys.any_just_pressed([KeyCode::Minus, KeyCode::NumpadSubtract]) {
+        -0.1
+    } else {
+        return;
+    };
+    pack.volume = (pack.volume + step).clamp(0.0, 1.0);
+    pack.muted = false;
+    ew_notice.send(Notice(format!("Volume: {:.0}%", pack.volume * 100.0)));
+}
+
+fn play_sounds(
+    mut commands: Commands,
+    mut er_sound: EventReader<PlaySound>,
+    pack: Option<Res<SoundPack>>,
+) {
+    let Some(pack) = pack else {
+        er_sound.clear();
+        return;
+    };
+    //Several sounds at once are only noise, so the last one wins.
+    let Some(PlaySound(sound)) = er_sound.read().last() else {return};
+    if pack.muted || pack.volume <= 0.0 {return;}
+    let settings = PlaybackSettings::DESPAWN.with_volume(Volume::new_relative(pack.volume));
+    match pack.sounds.iter().find(|(known, _)| known == sound).map(|(_, source)| source) {
+        Some(SoundSource::File(source)) => {commands.spawn(AudioBundle {source: source.clone(), settings});},
+        Some(SoundSource::Chime(source)) => {commands.spawn(AudioSourceBundle {source: source.clone(), settings});},
+        None => (),
+    }
+}
@@ -0,0 +1,124 @@
+use bevy::prelude::*;
+
+use super::{BoardChange, ChessPluginSettings, MoveFetch, get_opposing_move, updateboardstate};
+use crate::core::board::{Board, Move, Piece};
+use super::buttons::{shade_margin_button, spawn_margin_buttons};
+use super::movelist::BoardView;
+
+pub struct TakebackPlugin;
+
+impl Plugin for TakebackPlugin {
+    fn build(&self, app: &mut App) {
+        app
+        .insert_resource(TakebackHistory{line: Vec::new()})
+        .add_event::<Takeback>()
+        .add_systems(Startup, setup_takeback_buttons)
+        .add_systems(Update, (takeback_keys, takeback_buttons, handle_takeback.after(takeback_keys).after(takeback_buttons).before(get_opposing_move).before(updateboardstate)));
+    }
+}
+
+#[derive(Event,Clone,Copy,PartialEq)]
+pub enum Takeback{
+    Undo,
+    Redo,
+}
+
+#[derive(Component)]
+pub struct TakebackButton(Takeback);
+
+//The longest line of the game seen so far, which undone moves can be redone from.
+//It stops being usable as soon as a different move is played.
+#[derive(Resource)]
+pub struct TakebackHistory{
+    line: Vec<Move>,
+}
+
+impl TakebackHistory {
+    pub fn clear(&mut self) {
+        self.line.clear();
+    }
+
+    fn redo_move(&self, board: &Board) -> Option<Move> {
+        let played = board.movelist.len();
+        if self.line.len() <= played || self.line[..played] != board.movelist[..] {return None;}
+        Some(self.line[played])
+    }
+}
+
+fn setup_takeback_buttons(
+    mut commands: Commands,
+    settings: Res<ChessPluginSettings>,
+    asset_server: Res<AssetServer>,
+) {
+    if !settings.takebacks {return;}
+    let margins = settings.margins;
+    spawn_margin_buttons(&mut commands, &asset_server, margins, 100.0 - margins.bottom + 1.0, margins.bottom - 2.0, [
+        (TakebackButton(Takeback::Undo), "Undo"),
+        (TakebackButton(Takeback::Redo), "Redo"),
+    ]);
+}
+
+fn takeback_keys(keys: Res<Input<KeyCode>>, mut ew_takeback: EventWriter<Takeback>) {
+    if !keys.any_pressed([KeyCode::ControlLeft, KeyCode::ControlRight]) {return;}
+    if keys.just_pressed(KeyCode::Z) {
+        ew_takeback.send(Takeback::Undo);
+    } else if keys.just_pressed(KeyCode::Y) {
+        ew_takeback.send(Takeback::Redo);
+    }
+}
+
+fn takeback_buttons(
+    mut q_buttons: Query<(&Interaction, &TakebackButton, &mut BackgroundColor), Changed<Interaction>>,
+    mut ew_takeback: EventWriter<Takeback>,
+) {
+    for (interaction, button, mut color) in q_buttons.iter_mut() {
+        if shade_margin_button(*interaction, &mut color) {
+            ew_takeback.send(button.0);
+        }
+    }
+}
+
+fn handle_takeback(
+    mut commands: Commands,
+    mut er_takeback: EventReader<Takeback>,
+    mut board: ResMut<Board>,
+    mut history: ResMut<TakebackHistory>,
+    mut view: ResMut<BoardView>,
+    settings: Res<ChessPluginSettings>,
+    q_fetch: Query<Entity, With<MoveFetch>>,
+    mut ew_board: EventWriter<BoardChange>,
+) {
+    for action in er_takeback.read() {
+        //A finished game is locked however it ended, as its result is recorded once it is over.
+        if !settings.takebacks || board.outcome.is_some() {continue;}
+        //The engine's opening move as White is not the player's to take back.
+        if *action == Takeback::Undo && settings.engine && !board.movelist.iter().any(|bmove| bmove.color == settings.color) {continue;}
+        if history.redo_move(&board).is_none() {
+            history.line = board.movelist.clone();
+        }
+        let mut changed = false;
+        //Against the engine, step whole turns so it is the player to move again.
+        loop {
+            let stepped = match action {
+                Takeback::Undo => board.undo().is_some(),
+                Takeback::Redo => match history.redo_move(&board) {
+                    Some(bmove) => {
+                        board.make_move(bmove.from, bmove.to, bmove.promotion.map(|piece| Piece{piece, color: bmove.color}));
+                        true
+                    },
+                    None => false,
+                },
+            };
+            if !stepped {break;}
+            changed = true;
+            if !settings.engine || board.to_move() == settings.color {break;}
+        }
+        if !changed {continue;}
+        //Drop any engine search that was started from the old position.
+        for entity in q_fetch.iter() {
+            commands.entity(entity).despawn();
+        }
+        view.ply = None;
+        ew_board.send(BoardChange);
+    }
+}
@@ -0,0 +1,322 @@
+use std::{fmt, fs, path::Path};
+
+use bevy::{
+    asset::{AssetLoader, AsyncReadExt, LoadContext, LoadedFolder, io::{Reader, file::FileAssetReader}},
+    prelude::*,
+    utils::BoxedFuture,
+};
+use serde::{Deserialize, Serialize};
+
+use super::{BLACK_TILE, WHITE_TILE, BoardChange};
+use super::notice::Notice;
+use crate::core::square::Square;
+
+pub const LAST_MOVE_TINT:Color = Color::Rgba{red: 0.968627, green: 0.968627, blue: 0.411765, alpha: 0.5};
+pub const SELECTED_TINT:Color = Color::Rgba{red: 0.078431, green: 0.333333, blue: 0.117647, alpha: 0.5};
+
+pub struct ThemePlugin;
+
+impl Plugin for ThemePlugin {
+    fn build(&self, app: &mut App) {
+        app
+        .init_asset::<BoardTheme>()
+        .register_asset_loader(BoardThemeLoader)
+        .add_systems(Startup, setup_themes)
+        .add_systems(Update, (collect_themes, switch_theme, apply_theme.after(switch_theme)));
+    }
+}
+
+//Colors the board is drawn with. Highlights are tints laid over the square's own color.
+//Also an asset, so themes can be loaded from `.theme.ron` and `.theme.toml` files.
+#[derive(Resource,Asset,TypePath,Clone,PartialEq,Debug,Serialize,Deserialize)]
+pub struct BoardTheme{
+    pub name: String,
+    #[serde(with = "hex")]
+    pub light: Color,
+    #[serde(with = "hex")]
+    pub dark: Color,
+    #[serde(with = "hex")]
+    pub last_move: Color,
+    #[serde(with = "hex")]
+    pub selected: Color,
+    #[serde(with = "hex")]
+    pub coordinates: Color,
+    #[serde(with = "hex")]
+    pub background: Color,
+    //Tint of the glow behind a king in check.
+    #[serde(with = "hex")]
+    pub check: Color,
+    //Arrows drawn with no modifier, Shift, Alt and Shift+Alt held.
+    #[serde(with = "hex_array")]
+    pub arrows: [Color; 4],
+}
+
+impl Default for BoardTheme {
+    fn default() -> Self {
+        BoardTheme {
+            name: "Green".to_string(),
+            light: WHITE_TILE,
+            dark: BLACK_TILE,
+            last_move: LAST_MOVE_TINT,
+            selected: SELECTED_TINT,
+            coordinates: Color::WHITE,
+            background: ClearColor::default().0,
+            check: Color::WHITE,
+            arrows: [
+                Color::rgba(0.0,1.0,0.0,0.85),
+                Color::rgba(1.0,0.0,0.0,0.85),
+                Color::rgba(0.0,0.0,1.0,0.85),
+                Color::rgba(1.0,1.0,0.0,0.85),
+            ],
+        }
+    }
+}
+
+impl BoardTheme {
+    //Themes that are always available, whatever is in the themes folder.
+    pub fn presets() -> Vec<BoardTheme> {
+        let green = BoardTheme::default();
+        vec![
+            green.clone(),
+            BoardTheme {
+                name: "Brown".to_string(),
+                light: Color::rgb_u8(240, 217, 181),
+                dark: Color::rgb_u8(181, 136, 99),
+                last_move: Color::rgba_u8(155, 199, 0, 105),
+                selected: Color::rgba_u8(20, 85, 30, 128),
+                coordinates: Color::rgb_u8(240, 217, 181),
+                background: Color::rgb_u8(46, 42, 36),
+                ..green.clone()
+            },
+            BoardTheme {
+                name: "Blue".to_string(),
+                light: Color::rgb_u8(222, 227, 230),
+                dark: Color::rgb_u8(140, 162, 173),
+                last_move: Color::rgba_u8(155, 199, 0, 105),
+                selected: Color::rgba_u8(20, 85, 30, 128),
+                coordinates: Color::rgb_u8(222, 227, 230),
+                background: Color::rgb_u8(38, 43, 48),
+                ..green
+            },
+            BoardTheme {
+                name: "Paper".to_string(),
+                light: Color::rgb_u8(250, 250, 245),
+                dark: Color::rgb_u8(190, 190, 180),
+                last_move: Color::rgba_u8(100, 140, 220, 90),
+                selected: Color::rgba_u8(60, 60, 60, 90),
+                coordinates: Color::rgb_u8(60, 60, 60),
+                background: Color::rgb_u8(235, 235, 228),
+                check: Color::rgb_u8(255, 120, 120),
+                arrows: [
+                    Color::rgba(0.1,0.5,0.1,0.8),
+                    Color::rgba(0.7,0.1,0.1,0.8),
+                    Color::rgba(0.1,0.2,0.7,0.8),
+                    Color::rgba(0.8,0.6,0.0,0.8),
+                ],
+            },
+        ]
+    }
+
+    //Parse a theme as written in a `.theme.ron` or `.theme.toml` file.
+    pub fn parse(text: &str, toml: bool) -> Result<BoardTheme, ThemeError> {
+        if toml {
+            toml::from_str(text).map_err(ThemeError::Toml)
+        } else {
+            ron::from_str(text).map_err(ThemeError::Ron)
+        }
+    }
+
+    pub fn read(path: &Path) -> Result<BoardTheme, ThemeError> {
+        let text = fs::read_to_string(path).map_err(ThemeError::Io)?;
+        BoardTheme::parse(&text, path.extension().is_some_and(|extension| extension == "toml"))
+    }
+
+    //A preset or a theme in the themes folder, by name regardless of case.
+    pub fn named(name: &str) -> Option<BoardTheme> {
+        let folder = FileAssetReader::get_base_path().join("assets").join("themes");
+        let files = fs::read_dir(folder).into_iter().flatten().flatten()
+            .filter_map(|entry| BoardTheme::read(&entry.path()).ok());
+        BoardTheme::presets().into_iter().chain(files).find(|theme| theme.name.eq_ignore_ascii_case(name))
+    }
+
+    pub fn tile_color(&self, square: Square) -> Color {
+        if square.is_light() {self.light} else {self.dark}
+    }
+}
+
+//Paint a translucent color over an opaque one.
+pub fn blend(base: Color, tint: Color) -> Color {
+    let alpha = tint.a();
+    Color::rgb(
+        base.r() * (1.0 - alpha) + tint.r() * alpha,
+        base.g() * (1.0 - alpha) + tint.g() * alpha,
+        base.b() * (1.0 - alpha) + tint.b() * alpha,
+    )
+}
+
+//Colors are written as "#rrggbb" or "#rrggbbaa" in theme files.
+mod hex {
+    use bevy::prelude::Color;
+    use serde::{Deserialize, Deserializer, Serializer, de::Error};
+
+    pub fn to_string(color: &Color) -> String {
+        let [r, g, b, a] = color.as_rgba_u8();
+        if a == 255 {format!("#{:02x}{:02x}{:02x}", r, g, b)} else {format!("#{:02x}{:02x}{:02x}{:02x}", r, g, b, a)}
+    }
+
+    pub fn from_str<E: Error>(s: &str) -> Result<Color, E> {
+        Color::hex(s).map_err(|_| E::custom(format!("invalid color {}", s)))
+    }
+
+    pub fn serialize<S: Serializer>(color: &Color, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&to_string(color))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Color, D::Error> {
+        from_str(&String::deserialize(deserializer)?)
+    }
+}
+
+mod hex_array {
+    use bevy::prelude::Color;
+    use serde::{Deserialize, Deserializer, Serialize, Serializer, de::Error};
+
+    pub fn serialize<S: Serializer, const N: usize>(colors: &[Color; N], serializer: S) -> Result<S::Ok, S::Error> {
+        colors.iter().map(super::hex::to_string).collect::<Vec<_>>().serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>, const N: usize>(deserializer: D) -> Result<[Color; N], D::Error> {
+        let colors = Vec::<String>::deserialize(deserializer)?.iter()
+            .map(|s| super::hex::from_str(s))
+            .collect::<Result<Vec<_>, _>>()?;
+        let len = colors.len();
+        colors.try_into().map_err(|_| D::Error::invalid_length(len, &"one color per arrow modifier"))
+    }
+}
+
+#[derive(Debug)]
+pub enum ThemeError{
+    Io(std::io::Error),
+    Ron(ron::error::SpannedError),
+    Toml(toml::de::Error),
+}
+
+impl fmt::Display for ThemeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ThemeError::Io(e) => write!(f, "unable to read theme: {}", e),
+            ThemeError::Ron(e) => write!(f, "invalid theme: {}", e),
+            ThemeError::Toml(e) => write!(f, "invalid theme: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for ThemeError {}
+
+#[derive(Default)]
+pub struct BoardThemeLoader;
+
+impl AssetLoader for BoardThemeLoader {
+    type Asset = BoardTheme;
+    type Settings = ();
+    type Error = ThemeError;
+
+    fn load<'a>(
+        &'a self,
+        reader: &'a mut Reader,
+        _settings: &'a (),
+        load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<BoardTheme, ThemeError>> {
+        Box::pin(async move {
+            let mut text = String::new();
+            reader.read_to_string(&mut text).await.map_err(ThemeError::Io)?;
+            BoardTheme::parse(&text, load_context.path().extension().is_some_and(|extension| extension == "toml"))
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["theme.ron", "theme.toml"]
+    }
+}
+
+//Every theme that can be switched to, in the order the T key cycles through them.
+#[derive(Resource)]
+pub struct ThemeLibrary{
+    pub themes: Vec<BoardTheme>,
+    current: usize,
+    //Keeps the themes in the assets folder loaded.
+    _folder: Handle<LoadedFolder>,
+}
+
+impl ThemeLibrary {
+    fn add(&mut self, theme: BoardTheme) {
+        match self.themes.iter().position(|known| known.name == theme.name) {
+            Some(i) => self.themes[i] = theme,
+            None => self.themes.push(theme),
+        }
+    }
+}
+
+fn setup_themes(
+    mut commands: Commands,
+    theme: Res<BoardTheme>,
+    asset_server: Res<AssetServer>,
+) {
+    let mut library = ThemeLibrary {themes: BoardTheme::presets(), current: 0, _folder: asset_server.load_folder("themes")};
+    library.add(theme.clone());
+    library.current = library.themes.iter().position(|known| known.name == theme.name).unwrap_or(0);
+    commands.insert_resource(library);
+}
+
+fn collect_themes(
+    mut er_asset: EventReader<AssetEvent<BoardTheme>>,
+    themes: Res<Assets<BoardTheme>>,
+    library: Option<ResMut<ThemeLibrary>>,
+) {
+    let Some(mut library) = library else {return};
+    for event in er_asset.read() {
+        if let AssetEvent::Added {id} | AssetEvent::Modified {id} = event {
+            if let Some(theme) = themes.get(*id) {
+                library.add(theme.clone());
+            }
+        }
+    }
+}
+
+fn switch_theme(
+    keys: Res<Input<KeyCode>>,
+    library: Option<ResMut<ThemeLibrary>>,
+    mut theme: ResMut<BoardTheme>,
+    mut ew_board: EventWriter<BoardChange>,
+    mut ew_notice: EventWriter<Notice>,
+) {
+    let Some(mut library) = library else {return};
+    if !keys.just_pressed(KeyCode::T) || library.themes.is_empty() {return;}
+    library.current = (library.current + 1) % library.themes.len();
+    *theme = library.themes[library.current].clone();
+    ew_notice.send(Notice(format!("Theme: {}", theme.name)));
+    //Redraw the squares in their new colors.
+    ew_board.send(BoardChange);
+}
+
+#[derive(Component)]
+pub struct CoordinateText;
+
+//Recolor everything that is not redrawn on a board change.
+fn apply_theme(
+    theme: Res<BoardTheme>,
+    mut clear_color: ResMut<ClearColor>,
+    mut q_text: Query<&mut Text, With<CoordinateText>>,
+    mut q_check: Query<&mut Sprite, With<super::CheckMarker>>,
+) {
+    if !theme.is_changed() {return;}
+    clear_color.0 = theme.background;
+    for mut text in q_text.iter_mut() {
+        for section in text.sections.iter_mut() {
+            section.style.color = theme.coordinates;
+        }
+    }
+    for mut sprite in q_check.iter_mut() {
+        sprite.color = theme.check;
+    }
+}
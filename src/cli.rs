@@ -0,0 +1,237 @@
+use std::{fs, path::PathBuf, time::Duration};
+
+use clap::{error::ErrorKind, CommandFactory, Parser, ValueEnum};
+use rand::Rng;
+
+use chesslite::chess::ChessPluginSettings;
+use chesslite::chess::clock::TimeControl;
+use chesslite::chess::setup::StartPosition;
+use chesslite::chess::theme::BoardTheme;
+use chesslite::core::board::{Board, PieceColor};
+use chesslite::core::book::{BookSelection, BookSettings};
+use chesslite::core::pgn::read_pgn;
+use chesslite::core::syzygy::TablebaseSettings;
+use chesslite::core::uci::{EngineFallback, SearchLimit};
+
+#[derive(Clone,Copy,PartialEq,Debug,ValueEnum)]
+pub enum Side{
+    White,
+    Black,
+    Random,
+}
+
+//Anything given here skips the new game menu and starts that game straight away.
+#[derive(Parser,Debug)]
+#[command(version, about = "Play chess against Stockfish, the built-in engine or another player")]
+pub struct Cli{
+    #[arg(long, value_enum, help = "Side to play")]
+    pub color: Option<Side>,
+    #[arg(long, value_name = "PATH", help = "Stockfish or another UCI engine to play against")]
+    pub engine_path: Option<PathBuf>,
+    #[arg(long, value_name = "MS", conflicts_with = "depth", help = "Milliseconds the engine thinks per move")]
+    pub movetime: Option<u64>,
+    #[arg(long, value_name = "PLIES", help = "Depth the engine searches to each move")]
+    pub depth: Option<u32>,
+    #[arg(long, value_name = "FILE", help = "Polyglot opening book the computer plays from")]
+    pub book: Option<PathBuf>,
+    #[arg(long, value_name = "PLIES", help = "Plies from the start of the game the book is used for")]
+    pub book_depth: Option<usize>,
+    #[arg(long, value_name = "weighted|best", help = "Pick book moves at random by weight, or always the heaviest")]
+    pub book_selection: Option<BookSelection>,
+    #[arg(long, value_name = "DIRS", help = "Directories holding Syzygy tablebases, separated by ':' (';' on Windows)")]
+    pub syzygy_path: Option<String>,
+    #[arg(long, help = "End games as soon as the tablebases know the result")]
+    pub syzygy_adjudicate: bool,
+    #[arg(long, value_name = "SECONDS", help = "How long the engine may stop responding before it is restarted")]
+    pub engine_timeout: Option<f32>,
+    #[arg(long, value_name = "POLICY", help = "What plays when the engine can't: forfeit, builtin, builtin:<depth> or random")]
+    pub engine_fallback: Option<EngineFallback>,
+    #[arg(long, conflicts_with = "pgn", help = "Start from this position")]
+    pub fen: Option<String>,
+    #[arg(long, value_name = "FILE", help = "Carry on with the first game in this PGN file")]
+    pub pgn: Option<PathBuf>,
+    #[arg(long, value_name = "CONTROL", help = "Clock in PGN TimeControl syntax, e.g. 300+5 or 40/5400:1800+30")]
+    pub time_control: Option<TimeControl>,
+    #[arg(long, help = "Both sides are played at this screen")]
+    pub hotseat: bool,
+    #[arg(long, value_name = "NAME|FILE", help = "A board theme by name or a .theme.ron/.theme.toml file")]
+    pub theme: Option<String>,
+    #[arg(long, help = "Start in fullscreen")]
+    pub fullscreen: bool,
+}
+
+impl Cli {
+    //Write the flags over the defaults, along with the game to carry on with if one was loaded.
+    //Bad values end the program with a usage error, as clap does for flags it can't parse.
+    pub fn apply(&self, settings: &mut ChessPluginSettings) -> Option<Board> {
+        self.try_apply(settings).unwrap_or_else(|e| Cli::command().error(ErrorKind::ValueValidation, e).exit())
+    }
+
+    fn try_apply(&self, settings: &mut ChessPluginSettings) -> Result<Option<Board>, String> {
+        if let Some(side) = self.color {
+            settings.color = match side {
+                Side::White => PieceColor::White,
+                Side::Black => PieceColor::Black,
+                Side::Random => if rand::thread_rng().gen_bool(0.5) {PieceColor::White} else {PieceColor::Black},
+            };
+        }
+        if let Some(path) = &self.engine_path {
+            settings.engine_settings.path = path.clone();
+        }
+        if let Some(movetime) = self.movetime {
+            settings.engine_settings.limit = SearchLimit::MoveTime(Duration::from_millis(movetime));
+        }
+        if let Some(depth) = self.depth {
+            settings.engine_settings.limit = SearchLimit::Depth(depth);
+        }
+        if let Some(timeout) = self.engine_timeout {
+            settings.engine_settings.timeout = Duration::try_from_secs_f32(timeout).map_err(|_| format!("invalid --engine-timeout: {}", timeout))?;
+        }
+        if let Some(fallback) = self.engine_fallback {
+            settings.engine_settings.fallback = fallback;
+        }
+        if let Some(path) = &self.book {
+            let path = path.to_string_lossy().into_owned();
+            //A depth and selection from the settings carry over to the book given here.
+            settings.book = Some(match settings.book.take() {
+                Some(book) => BookSettings {path, ..book},
+                None => BookSettings::new(path),
+            });
+        }
+        if self.book_depth.is_some() || self.book_selection.is_some() {
+            let book = settings.book.as_mut().ok_or("--book-depth and --book-selection need a book, from --book or the settings")?;
+            book.max_depth = self.book_depth.unwrap_or(book.max_depth);
+            book.selection = self.book_selection.unwrap_or(book.selection);
+        }
+        if let Some(path) = &self.syzygy_path {
+            let adjudicate = settings.tablebase.as_ref().is_some_and(|tablebase| tablebase.adjudicate);
+            settings.tablebase = Some(TablebaseSettings {path: path.clone(), adjudicate});
+        }
+        if self.syzygy_adjudicate {
+            let tablebase = settings.tablebase.as_mut().ok_or("--syzygy-adjudicate needs tablebases, from --syzygy-path or the settings")?;
+            tablebase.adjudicate = true;
+        }
+        if let Some(fen) = &self.fen {
+            Board::from_fen(fen).map_err(|e| format!("invalid --fen: {}", e))?;
+            settings.start_position = StartPosition::Fen(fen.clone());
+        }
+        let game = match &self.pgn {
+            Some(path) => {
+                let text = fs::read_to_string(path).map_err(|e| format!("unable to read {}: {}", path.display(), e))?;
+                let board = read_pgn(&text).map_err(|e| format!("invalid PGN in {}: {}", path.display(), e))?;
+                //New games from the menu start where this one did.
+                settings.start_position = board.start_fen.clone().map_or(StartPosition::Standard, StartPosition::Fen);
+                settings.variant = board.variant;
+                Some(board)
+            },
+            None => None,
+        };
+        if let Some(time_control) = &self.time_control {
+            settings.time_control = Some(time_control.clone());
+        }
+        if self.hotseat {
+            settings.engine = false;
+        }
+        if let Some(theme) = &self.theme {
+            let path = PathBuf::from(theme);
+            settings.theme = if path.is_file() {
+                BoardTheme::read(&path).map_err(|e| format!("{}: {}", path.display(), e))?
+            } else {
+                BoardTheme::named(theme).ok_or_else(|| format!("no theme called {}", theme))?
+            };
+        }
+        settings.setup_menu = !self.configures_game();
+        Ok(game)
+    }
+
+    fn configures_game(&self) -> bool {
+        self.color.is_some() || self.movetime.is_some() || self.depth.is_some() || self.fen.is_some()
+            || self.pgn.is_some() || self.time_control.is_some() || self.hotseat
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use clap::{error::ErrorKind, Parser};
+
+    use chesslite::chess::ChessPluginSettings;
+    use chesslite::chess::setup::StartPosition;
+    use chesslite::core::book::{BookSelection, BookSettings};
+    use chesslite::core::uci::SearchLimit;
+
+    use super::Cli;
+
+    fn apply(args: &[&str]) -> Result<ChessPluginSettings, String> {
+        apply_to(crate::default_settings(), args)
+    }
+
+    fn apply_to(mut settings: ChessPluginSettings, args: &[&str]) -> Result<ChessPluginSettings, String> {
+        let cli = Cli::try_parse_from(["chesslite"].iter().chain(args)).map_err(|e| e.to_string())?;
+        cli.try_apply(&mut settings)?;
+        Ok(settings)
+    }
+
+    fn apply_error(args: &[&str]) -> String {
+        apply(args).err().unwrap()
+    }
+
+    fn parse_error(args: &[&str]) -> ErrorKind {
+        Cli::try_parse_from(["chesslite"].iter().chain(args)).unwrap_err().kind()
+    }
+
+    #[test]
+    fn opens_the_menu_unless_a_game_is_given() {
+        assert!(apply(&[]).unwrap().setup_menu);
+        assert!(apply(&["--theme", "brown"]).unwrap().setup_menu);
+        let settings = apply(&["--hotseat"]).unwrap();
+        assert!(!settings.setup_menu && !settings.engine);
+        assert!(!apply(&["--color", "black"]).unwrap().setup_menu);
+    }
+
+    #[test]
+    fn sets_the_engine_limit() {
+        assert_eq!(apply(&["--movetime", "250"]).unwrap().engine_settings.limit, SearchLimit::MoveTime(Duration::from_millis(250)));
+        assert_eq!(apply(&["--depth", "12"]).unwrap().engine_settings.limit, SearchLimit::Depth(12));
+        assert_eq!(parse_error(&["--movetime", "250", "--depth", "12"]), ErrorKind::ArgumentConflict);
+        assert!(apply_error(&["--engine-timeout=-1"]).contains("--engine-timeout"));
+    }
+
+    #[test]
+    fn book_options_need_a_book() {
+        assert!(apply_error(&["--book-depth", "8"]).contains("need a book"));
+        assert!(apply_error(&["--book-selection", "best"]).contains("need a book"));
+        let book = apply(&["--book", "book.bin", "--book-depth", "8", "--book-selection", "best"]).unwrap().book.unwrap();
+        assert_eq!((book.path.as_str(), book.max_depth, book.selection), ("book.bin", 8, BookSelection::BestWeight));
+        //A book from the settings keeps its options when only the file is changed.
+        let mut settings = crate::default_settings();
+        settings.book = Some(BookSettings {path: "old.bin".to_string(), selection: BookSelection::BestWeight, max_depth: 4});
+        let book = apply_to(settings, &["--book", "new.bin"]).unwrap().book.unwrap();
+        assert_eq!((book.path.as_str(), book.max_depth, book.selection), ("new.bin", 4, BookSelection::BestWeight));
+    }
+
+    #[test]
+    fn adjudication_needs_tablebases() {
+        assert!(apply_error(&["--syzygy-adjudicate"]).contains("needs tablebases"));
+        let tablebase = apply(&["--syzygy-path", "tables", "--syzygy-adjudicate"]).unwrap().tablebase.unwrap();
+        assert!(tablebase.path == "tables" && tablebase.adjudicate);
+    }
+
+    #[test]
+    fn checks_the_start_position() {
+        let fen = "4k3/8/8/8/8/8/8/4K2R w K - 0 1";
+        assert_eq!(apply(&["--fen", fen]).unwrap().start_position, StartPosition::Fen(fen.to_string()));
+        assert!(apply_error(&["--fen", "not a position"]).contains("invalid --fen"));
+        assert_eq!(parse_error(&["--fen", fen, "--pgn", "game.pgn"]), ErrorKind::ArgumentConflict);
+        assert!(apply_error(&["--pgn", "no/such/game.pgn"]).contains("unable to read"));
+    }
+
+    #[test]
+    fn rejects_bad_values() {
+        assert!(apply_error(&["--theme", "no such theme"]).contains("no theme called"));
+        assert_eq!(parse_error(&["--time-control", "abc"]), ErrorKind::ValueValidation);
+        assert_eq!(parse_error(&["--engine-fallback", "sometimes"]), ErrorKind::ValueValidation);
+        assert_eq!(parse_error(&["--color", "green"]), ErrorKind::InvalidValue);
+    }
+}
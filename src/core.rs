@@ -0,0 +1,15 @@
+//The rules and everything that only needs them, with no Bevy: usable on its own with default features turned off.
+
+pub mod board;
+
+pub mod square;
+
+pub mod book;
+
+pub mod syzygy;
+
+pub mod search;
+
+pub mod pgn;
+
+pub mod uci;
@@ -0,0 +1,1113 @@
+use std::{fmt, ops::{Index, IndexMut, Not}};
+
+use super::square::{File, Rank, Square};
+
+pub const MATE_SCORE: i32 = 100000;
+
+#[derive(Clone,Copy,PartialEq,Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum GameOverState{
+    AgreedDraw,
+    Stalemate,
+    Checkmate(PieceColor),
+    //The color resigned.
+    Resignation(PieceColor),
+    //Result decided from outside the board, e.g. by a tablebase. None is a draw.
+    Adjudication(Option<PieceColor>),
+    //The color ran out of time.
+    Timeout(PieceColor),
+    //The color ran out of time, but the opponent could never have mated, so it is a draw.
+    TimeoutVsInsufficientMaterial(PieceColor),
+    //The same position came up for the third time.
+    Repetition,
+    //Fifty moves each without a capture or a pawn move.
+    FiftyMoves,
+    //Neither side has the material left to ever checkmate.
+    InsufficientMaterial,
+    //The color got its king to the center.
+    KingOfTheHill(PieceColor),
+    //The color gave check for the third time.
+    ThreeCheck(PieceColor),
+    //The color's engine stopped answering and the game was given up for it.
+    Forfeit(PieceColor),
+    Ongoing
+}
+
+impl GameOverState{
+    //The color that won, None for a draw or a game that is still going.
+    pub fn winner(&self) -> Option<PieceColor> {
+        match *self {
+            GameOverState::Checkmate(color) | GameOverState::KingOfTheHill(color) | GameOverState::ThreeCheck(color) => Some(color),
+            GameOverState::Resignation(color) | GameOverState::Timeout(color) | GameOverState::Forfeit(color) => Some(!color),
+            GameOverState::Adjudication(winner) => winner,
+            _ => None,
+        }
+    }
+}
+
+//Rules on top of normal chess. Stockfish only knows the standard rules, so it plays the others as if they were standard.
+#[derive(Clone,Copy,PartialEq,Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Variant{
+    Standard,
+    //Getting the king to d4, e4, d5 or e5 also wins.
+    KingOfTheHill,
+    //Checking the opposing king three times also wins.
+    ThreeCheck,
+}
+
+#[derive(Clone,Copy,PartialEq,Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum PieceType{
+    King,
+    Queen,
+    Rook,
+    Bishop,
+    Knight,
+    Pawn
+}
+
+impl PieceType{
+    pub fn to_char(&self) -> char {
+        match self {
+            PieceType::King => 'k',
+            PieceType::Queen => 'q',
+            PieceType::Rook => 'r',
+            PieceType::Bishop => 'b',
+            PieceType::Knight => 'n',
+            PieceType::Pawn => 'p'
+        }
+    }
+}
+
+#[derive(Clone,Copy,PartialEq,Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum PieceColor{
+    Black,
+    White
+}
+
+impl PieceColor{
+    fn to_char(self) -> char {
+        if let PieceColor::White = self {'w'} else {'b'}
+    }
+
+    fn index(&self) -> usize {
+        if let PieceColor::White = self {0} else {1}
+    }
+
+    fn home_rank(&self) -> Rank {
+        if let PieceColor::White = self {Rank::FIRST} else {Rank::EIGHTH}
+    }
+
+    //Where the pawns start.
+    fn pawn_rank(&self) -> Rank {
+        if let PieceColor::White = self {Rank::SECOND} else {Rank::SEVENTH}
+    }
+
+    //Ranks a pawn moves up the board, so -1 for Black.
+    fn forward(&self) -> i32 {
+        if let PieceColor::White = self {1} else {-1}
+    }
+}
+
+impl Not for PieceColor{
+    type Output = Self;
+
+    fn not(self) -> Self::Output {
+        match self {
+            PieceColor::Black => PieceColor::White,
+            PieceColor::White => PieceColor::Black,
+        }
+    }
+}
+
+#[derive(Clone,Copy,PartialEq,Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Piece {
+    pub piece: PieceType,
+    pub color: PieceColor
+}
+
+impl Piece{
+    fn from_char(c: char) -> Option<Self> {
+        let piece = match c.to_ascii_lowercase() {
+            'k' => PieceType::King,
+            'q' => PieceType::Queen,
+            'r' => PieceType::Rook,
+            'b' => PieceType::Bishop,
+            'n' => PieceType::Knight,
+            'p' => PieceType::Pawn,
+            _ => return None,
+        };
+        let color = if c.is_ascii_uppercase() {PieceColor::White} else {PieceColor::Black};
+        Some(Piece{piece, color})
+    }
+
+    fn to_char(self) -> char {
+        let c = match self.piece {
+            PieceType::King => 'k',
+            PieceType::Queen => 'q',
+            PieceType::Rook => 'r',
+            PieceType::Bishop => 'b',
+            PieceType::Knight => 'n',
+            PieceType::Pawn => 'p'
+        };
+        if let PieceColor::White = self.color {
+            return c.to_uppercase().next().unwrap();
+        }
+        c
+    }
+}
+
+
+pub struct CastleCheck {
+    pub cancastle: bool,
+    pub rooksource: Option<Square>,
+    pub rookdestination: Option<Square>,
+    pub kingdestination: Option<Square>,
+}
+
+impl CastleCheck {
+    pub fn new(cancastle:bool) -> Self {
+        CastleCheck {
+            cancastle,
+            rooksource: None,
+            rookdestination: None,
+            kingdestination: None,
+        }
+    }
+}
+
+#[derive(Clone,Copy,PartialEq,Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SimpleMove {
+    pub from: Square,
+    pub to: Square,
+    pub promotion: Option<PieceType>,
+}
+
+//Why a move in UCI notation, such as an engine's reply, was turned down.
+#[derive(Clone,PartialEq,Debug)]
+pub enum ParseError{
+    //Not written as a move at all.
+    Malformed(String),
+    //A move, but not one that can be played in the position.
+    Illegal(String),
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseError::Malformed(uci) => write!(f, "'{}' is not a move", uci),
+            ParseError::Illegal(uci) => write!(f, "{} is not a legal move", uci),
+        }
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+impl SimpleMove {
+    //A move in UCI notation, such as e2e4 or e7e8q, without checking it against any position. The null move 0000 is None.
+    //Chess960 castling is the king taking its own rook, e.g. e1h1, which reads as any other move.
+    pub fn from_uci(uci: &str) -> Result<Option<Self>, ParseError> {
+        if uci == "0000" {return Ok(None);}
+        let malformed = || ParseError::Malformed(uci.to_string());
+        if !uci.is_ascii() || !(4..=5).contains(&uci.len()) {return Err(malformed());}
+        let from: Square = uci[0..2].parse().map_err(|_| malformed())?;
+        let to: Square = uci[2..4].parse().map_err(|_| malformed())?;
+        let promotion = match &uci[4..] {
+            "" => None,
+            "q" => Some(PieceType::Queen),
+            "r" => Some(PieceType::Rook),
+            "b" => Some(PieceType::Bishop),
+            "n" => Some(PieceType::Knight),
+            _ => return Err(malformed()),
+        };
+        if from == to {return Err(malformed());}
+        Ok(Some(Self {from, to, promotion}))
+    }
+
+    pub fn to_algebraic(&self) -> String {
+        let mut out = format!("{}{}", self.from, self.to);
+        if let Some(piece) = self.promotion {
+            out.push(piece.to_char())
+        }
+        out
+    }
+}
+
+#[derive(Clone,Copy,PartialEq,Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Move {
+    pub from: Square,
+    pub to: Square,
+    pub color: PieceColor,
+    pub piece: Piece,
+    pub capture: bool,
+    pub promotion: Option<PieceType>,
+}
+
+#[derive(Clone,Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CastleAvailability{
+    pub white_kingside: bool,
+    pub white_queenside: bool,
+    pub black_kingside: bool,
+    pub black_queenside: bool,
+    //Files the kings start on, [white, black]. Only Chess960 moves them off the e-file.
+    pub kings: [File;2],
+    //Files of the rooks each side castles with, [queenside, kingside] for [white, black].
+    pub rooks: [[File;2];2],
+}
+
+impl Default for CastleAvailability {
+    fn default() -> Self {
+        CastleAvailability::new()
+    }
+}
+
+impl CastleAvailability {
+    pub fn new() -> Self {
+        CastleAvailability {
+            white_kingside: true,
+            white_queenside: true,
+            black_kingside: true,
+            black_queenside: true,
+            kings: [File::E, File::E],
+            rooks: [[File::A, File::H], [File::A, File::H]],
+        }
+    }
+
+    pub fn none() -> Self {
+        CastleAvailability {
+            white_kingside: false,
+            white_queenside: false,
+            black_kingside: false,
+            black_queenside: false,
+            ..CastleAvailability::new()
+        }
+    }
+
+    //Kings on e and rooks in the corners, where KQkq says everything.
+    pub fn is_standard(&self) -> bool {
+        self.kings == [File::E, File::E] && self.rooks == [[File::A, File::H], [File::A, File::H]]
+    }
+
+    pub fn set_availability(&mut self, color: PieceColor, kingside: bool, availability: bool){
+        match color {
+            PieceColor::Black => {
+                if kingside {self.black_kingside = availability} else {self.black_queenside = availability}
+            },
+            PieceColor::White => {
+                if kingside {self.white_kingside = availability} else {self.white_queenside = availability}
+            }
+        }
+    }
+
+    pub fn any_castle(&self, color: PieceColor) -> bool {
+        match color {
+            PieceColor::Black => {
+                self.black_kingside || self.black_queenside
+            },
+            PieceColor::White => {
+                self.white_kingside || self.white_queenside
+            }
+        }
+    }
+
+    pub fn check_availability(&self, color: PieceColor, kingside: bool) -> bool {
+        match color {
+            PieceColor::Black => {if kingside {self.black_kingside} else {self.black_queenside}},
+            PieceColor::White => {if kingside {self.white_kingside} else {self.white_queenside}}
+        }
+    }
+}
+
+#[derive(Clone)]
+#[cfg_attr(feature = "bevy", derive(bevy::prelude::Resource))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Board {
+    pub tiles: [[Option<Piece>;8];8],
+    pub lastmove: Option<Move>,
+    pub movelist: Vec<Move>,
+    pub halfmoves: usize,
+    pub fullmoves: usize,
+    pub castles: CastleAvailability,
+    pub outcome: Option<GameOverState>,
+    //Side to move before any move was played, which a FEN can make Black.
+    pub first_to_move: PieceColor,
+    pub variant: Variant,
+    //Checks given by [white, black], for Three-check.
+    pub checks: [u8;2],
+    //Where the game started if not from the standard position.
+    pub start_fen: Option<String>,
+}
+
+//The piece on a square, as `tiles[rank][file]`.
+impl Index<Square> for Board {
+    type Output = Option<Piece>;
+
+    fn index(&self, square: Square) -> &Self::Output {
+        &self.tiles[square.rank().index()][square.file().index()]
+    }
+}
+
+impl IndexMut<Square> for Board {
+    fn index_mut(&mut self, square: Square) -> &mut Self::Output {
+        &mut self.tiles[square.rank().index()][square.file().index()]
+    }
+}
+
+impl Default for Board {
+    fn default() -> Self {
+        Board::new()
+    }
+}
+
+impl Board {
+    pub fn new() -> Self {
+        Board {
+            tiles: [
+            [Some(Piece{piece: PieceType::Rook, color: PieceColor::White}),Some(Piece{piece: PieceType::Knight, color: PieceColor::White}),Some(Piece{piece: PieceType::Bishop, color: PieceColor::White}), Some(Piece{piece: PieceType::Queen, color: PieceColor::White}), Some(Piece{piece: PieceType::King, color: PieceColor::White}), Some(Piece{piece: PieceType::Bishop, color: PieceColor::White}), Some(Piece{piece: PieceType::Knight, color: PieceColor::White}), Some(Piece{piece: PieceType::Rook, color: PieceColor::White})],
+            [Some(Piece{piece: PieceType::Pawn, color: PieceColor::White});8],
+            [None;8],
+            [None;8],
+            [None;8],
+            [None;8],
+            [Some(Piece{piece: PieceType::Pawn, color: PieceColor::Black});8],
+            [Some(Piece{piece: PieceType::Rook, color: PieceColor::Black}),Some(Piece{piece: PieceType::Knight, color: PieceColor::Black}),Some(Piece{piece: PieceType::Bishop, color: PieceColor::Black}), Some(Piece{piece: PieceType::Queen, color: PieceColor::Black}), Some(Piece{piece: PieceType::King, color: PieceColor::Black}), Some(Piece{piece: PieceType::Bishop, color: PieceColor::Black}), Some(Piece{piece: PieceType::Knight, color: PieceColor::Black}), Some(Piece{piece: PieceType::Rook, color: PieceColor::Black})],
+            ],
+            lastmove: None,
+            movelist: Vec::new(),
+            halfmoves: 0usize,
+            fullmoves: 1usize,
+            castles: CastleAvailability::new(),
+            outcome: None,
+            first_to_move: PieceColor::White,
+            variant: Variant::Standard,
+            checks: [0, 0],
+            start_fen: None,
+        }
+    }
+
+    //Read a position from Forsyth-Edwards Notation. Castling may be given as KQkq or, for Chess960, by rook files as in HAha.
+    pub fn from_fen(fen: &str) -> Result<Self, String> {
+        let fields: Vec<&str> = fen.split_whitespace().collect();
+        if fields.len() < 2 || fields.len() > 6 {return Err("a FEN needs between 2 and 6 fields".to_string());}
+        let mut board = Board::new();
+        board.castles = CastleAvailability::none();
+
+        let ranks: Vec<&str> = fields[0].split('/').collect();
+        if ranks.len() != 8 {return Err("the position needs 8 ranks".to_string());}
+        for (i, rank) in ranks.iter().enumerate() {
+            let x = 7 - i;
+            let mut y = 0;
+            board.tiles[x] = [None;8];
+            for c in rank.chars() {
+                if let Some(empty) = c.to_digit(10) {
+                    y += empty as usize;
+                } else {
+                    let piece = Piece::from_char(c).ok_or(format!("unknown piece '{}'", c))?;
+                    if y < 8 {board.tiles[x][y] = Some(piece);}
+                    y += 1;
+                }
+                if y > 8 {break;}
+            }
+            if y != 8 {return Err(format!("rank {} does not have 8 squares", x + 1));}
+        }
+        for color in [PieceColor::White, PieceColor::Black] {
+            let kings = board.tiles.iter().flatten().filter(|tile| **tile == Some(Piece{piece: PieceType::King, color})).count();
+            if kings != 1 {return Err("each side needs exactly one king".to_string());}
+        }
+        if [0, 7].iter().any(|x| board.tiles[*x].iter().any(|tile| tile.is_some_and(|piece| piece.piece == PieceType::Pawn))) {
+            return Err("pawns can't be on the first or last rank".to_string());
+        }
+
+        board.first_to_move = match fields[1] {
+            "w" => PieceColor::White,
+            "b" => PieceColor::Black,
+            _ => return Err(format!("'{}' is not a side to move", fields[1])),
+        };
+        if board.is_check(!board.first_to_move) {return Err("the side that just moved is in check".to_string());}
+
+        let castling = fields.get(2).copied().unwrap_or("-");
+        if castling != "-" {
+            for c in castling.chars() {
+                let color = if c.is_ascii_uppercase() {PieceColor::White} else {PieceColor::Black};
+                let home = color.home_rank();
+                let king = File::ALL.into_iter().find(|file| board[Square::new(*file, home)] == Some(Piece{piece: PieceType::King, color}))
+                    .ok_or(format!("castling '{}' needs the king on its first rank", c))?;
+                let is_rook = |file: &File| board[Square::new(*file, home)] == Some(Piece{piece: PieceType::Rook, color});
+                //K and Q mean the outermost rook on that side.
+                let rook = match c.to_ascii_lowercase() {
+                    'k' => File::ALL.into_iter().rev().take_while(|file| *file > king).find(is_rook),
+                    'q' => File::ALL.into_iter().take_while(|file| *file < king).find(is_rook),
+                    'a'..='h' => File::from_char(c.to_ascii_lowercase()).filter(is_rook),
+                    _ => return Err(format!("'{}' is not a castling right", c)),
+                }.ok_or(format!("castling '{}' has no rook to castle with", c))?;
+                let kingside = rook > king;
+                board.castles.kings[color.index()] = king;
+                board.castles.rooks[color.index()][kingside as usize] = rook;
+                board.castles.set_availability(color, kingside, true);
+            }
+        }
+
+        let en_passant = fields.get(3).copied().unwrap_or("-");
+        if en_passant != "-" {
+            let square: Square = en_passant.parse().map_err(|e| format!("{}", e))?;
+            //The pawn that just moved two squares, which is all en passant needs to know.
+            let color = !board.first_to_move;
+            let from = Square::new(square.file(), color.pawn_rank());
+            let to = Square::new(square.file(), if let PieceColor::White = color {Rank::FOURTH} else {Rank::FIFTH});
+            let pawn = Piece{piece: PieceType::Pawn, color};
+            if from.offset(0, color.forward()) != Some(square) || board[to] != Some(pawn) {
+                return Err(format!("no pawn can be taken en passant on {}", en_passant));
+            }
+            board.lastmove = Some(Move{from, to, color, piece: pawn, capture: false, promotion: None});
+        }
+
+        board.halfmoves = fields.get(4).map_or(Ok(0), |halfmoves| halfmoves.parse()).map_err(|_| "the halfmove clock is not a number".to_string())?;
+        board.fullmoves = fields.get(5).map_or(Ok(1), |fullmoves| fullmoves.parse()).map_err(|_| "the move number is not a number".to_string())?.max(1);
+
+        let fen = board.fen();
+        if fen != Board::new().fen() {board.start_fen = Some(fen);}
+        Ok(board)
+    }
+
+    //Starting position number `n` of Chess960, from 0 to 959 as numbered by Reinhard Scharnagl. 518 is the standard position.
+    pub fn chess960(n: usize) -> Self {
+        let mut rank: [Option<PieceType>;8] = [None;8];
+        let empty = |rank: &[Option<PieceType>;8]| (0..8).filter(|file| rank[*file].is_none()).collect::<Vec<_>>();
+        let (n, light) = (n % 960 / 4, n % 4);
+        rank[light * 2 + 1] = Some(PieceType::Bishop);
+        let (n, dark) = (n / 4, n % 4);
+        rank[dark * 2] = Some(PieceType::Bishop);
+        let (n, queen) = (n / 6, n % 6);
+        rank[empty(&rank)[queen]] = Some(PieceType::Queen);
+        let knights = [(0,1),(0,2),(0,3),(0,4),(1,2),(1,3),(1,4),(2,3),(2,4),(3,4)][n];
+        let free = empty(&rank);
+        rank[free[knights.0]] = Some(PieceType::Knight);
+        rank[free[knights.1]] = Some(PieceType::Knight);
+        //The king always goes between the rooks.
+        let free = empty(&rank);
+        for (file, piece) in free.iter().zip([PieceType::Rook, PieceType::King, PieceType::Rook]) {
+            rank[*file] = Some(piece);
+        }
+
+        let mut board = Board::new();
+        for (file, piece) in rank.iter().enumerate() {
+            let piece = piece.unwrap();
+            board.tiles[0][file] = Some(Piece{piece, color: PieceColor::White});
+            board.tiles[7][file] = Some(Piece{piece, color: PieceColor::Black});
+        }
+        board.castles.kings = [File::ALL[free[1]]; 2];
+        board.castles.rooks = [[File::ALL[free[0]], File::ALL[free[2]]]; 2];
+        if !board.castles.is_standard() {board.start_fen = Some(board.fen());}
+        board
+    }
+
+    //The position the game started from, before any of the moves in the list.
+    pub fn start_position(&self) -> Board {
+        let mut board = self.start_fen.as_deref().and_then(|fen| Board::from_fen(fen).ok()).unwrap_or_default();
+        board.variant = self.variant;
+        board
+    }
+
+    pub fn fen(&self) -> String {
+        let mut out = String::new();
+        for i in (0..8).rev() {
+            let rank = self.tiles[i];
+            let mut empty = 0;
+            for (num, square) in rank.into_iter().enumerate() {
+                if let Some(piece) = square {
+                    if empty > 0 {
+                        out += &empty.to_string();
+                        empty = 0;
+                    }
+                    out.push(piece.to_char());
+                } else {
+                    empty += 1;
+                    if num == 7 {
+                        out += &empty.to_string();
+                    }
+                }
+            }
+            if i != 0 {
+                out.push('/');
+            }
+        }
+        out += " ";
+        out.push(self.to_move().to_char());
+        out += " ";
+
+        let chars = ['K','Q','k','q'];
+
+        let mut anycastles = false;
+
+        for char in chars {
+            let color = if char.is_ascii_uppercase() {PieceColor::White} else {PieceColor::Black};
+            let kingside = char.eq_ignore_ascii_case(&'k');
+            if !self.castles.check_availability(color, kingside) {continue;}
+            anycastles = true;
+            if self.castles.is_standard() {
+                out.push(char);
+            } else {
+                //Chess960 names the rook by its file instead.
+                let file = self.castles.rooks[color.index()][kingside as usize].to_char();
+                out.push(if let PieceColor::White = color {file.to_ascii_uppercase()} else {file});
+            }
+        }
+
+        if !anycastles {
+            out.push('-');
+        }
+
+        out += " ";
+
+        let mut en_passant = "-".to_string();
+
+        if let Some(lastmove) = self.lastmove {
+            if [Rank::SECOND, Rank::SEVENTH].contains(&lastmove.from.rank()) && [Rank::FOURTH, Rank::FIFTH].contains(&lastmove.to.rank()) {
+                if let Some(piece) = self[lastmove.to] {
+                    if let PieceType::Pawn = piece.piece {
+                        let rank = if lastmove.to.rank() == Rank::FOURTH {Rank::THIRD} else {Rank::SIXTH};
+                        en_passant = Square::new(lastmove.to.file(), rank).to_string();
+                    }
+                }
+            }
+        }
+
+        out += &en_passant;
+
+        out += " ";
+
+        out += &self.halfmoves.to_string();
+
+        out += " ";
+
+        out += &self.fullmoves.to_string();
+
+        out
+    }
+
+    pub fn make_simple_move(&mut self, bmove: &SimpleMove) {
+        let promote = bmove.promotion.map(|piece| Piece{piece, color: self.to_move()});
+        self.make_move(bmove.from, bmove.to, promote);
+    }
+
+    pub fn legal_moves(&self) -> Vec<SimpleMove> {
+        Square::all().flat_map(|from| self.legal_moves_from(from)).collect()
+    }
+
+    pub fn legal_moves_from(&self, from: Square) -> Vec<SimpleMove> {
+        let mut moves = Vec::new();
+        let Some(piece) = self[from] else {return moves};
+        if self.to_move() != piece.color {return moves;}
+        for to in Square::all() {
+            if !self.is_legal_move(from, to) {continue;}
+            //Castling onto the rook is the same move as castling to the king's square, when that is a move of its own.
+            if piece.piece == PieceType::King && self[to].is_some_and(|other| other.color == piece.color) {
+                let king_square = Square::new(if to.file() > from.file() {File::G} else {File::C}, to.rank());
+                if king_square != to && self.can_castle(from, king_square).cancastle {continue;}
+            }
+            if piece.piece == PieceType::Pawn && (to.rank() == Rank::FIRST || to.rank() == Rank::EIGHTH) {
+                for promotion in [PieceType::Queen, PieceType::Rook, PieceType::Bishop, PieceType::Knight] {
+                    moves.push(SimpleMove {from, to, promotion: Some(promotion)});
+                }
+            } else {
+                moves.push(SimpleMove {from, to, promotion: None});
+            }
+        }
+        moves
+    }
+
+    //The position after the first `plies` moves of the game, replayed from the start.
+    pub fn replay(&self, plies: usize) -> Board {
+        let mut board = self.start_position();
+        for bmove in self.movelist.iter().take(plies) {
+            board.make_move(bmove.from, bmove.to, bmove.promotion.map(|piece| Piece{piece, color: bmove.color}));
+        }
+        board
+    }
+
+    //Take back the last move, returning it.
+    pub fn undo(&mut self) -> Option<Move> {
+        let bmove = *self.movelist.last()?;
+        *self = self.replay(self.movelist.len() - 1);
+        Some(bmove)
+    }
+
+    //Standard algebraic notation for a legal move in this position.
+    pub fn san(&self, bmove: &SimpleMove) -> String {
+        let Some(piece) = self[bmove.from] else {return String::new()};
+        let mut out = String::new();
+        let ccheck = self.can_castle(bmove.from, bmove.to);
+        if let (true, Some(rook)) = (ccheck.cancastle, ccheck.rooksource) {
+            out += if rook.file() > bmove.from.file() {"O-O"} else {"O-O-O"};
+        } else {
+            let capture = self[bmove.to].is_some() || self.is_en_passant(bmove.from, bmove.to);
+            if piece.piece == PieceType::Pawn {
+                if capture {out.push(bmove.from.file().to_char());}
+            } else {
+                out.push(piece.piece.to_char().to_ascii_uppercase());
+                //Other pieces of the same kind that could also reach the square.
+                let rivals: Vec<Square> = Square::all()
+                    .filter(|rival| *rival != bmove.from && self[*rival] == Some(piece) && self.is_legal_move(*rival, bmove.to))
+                    .collect();
+                if !rivals.is_empty() {
+                    if rivals.iter().all(|rival| rival.file() != bmove.from.file()) {
+                        out.push(bmove.from.file().to_char());
+                    } else if rivals.iter().all(|rival| rival.rank() != bmove.from.rank()) {
+                        out.push(bmove.from.rank().to_char());
+                    } else {
+                        out += &bmove.from.to_string();
+                    }
+                }
+            }
+            if capture {out.push('x');}
+            out += &bmove.to.to_string();
+            if let Some(promotion) = bmove.promotion {
+                out.push('=');
+                out.push(promotion.to_char().to_ascii_uppercase());
+            }
+        }
+        let mut after = self.clone();
+        after.make_simple_move(bmove);
+        if after.is_check(!piece.color) {
+            out.push(if after.any_legal_moves() {'+'} else {'#'});
+        }
+        out
+    }
+
+    //The legal move written in standard algebraic notation, forgiving extra disambiguation, a missing '=' and 0-0 for O-O.
+    pub fn parse_san(&self, san: &str) -> Option<SimpleMove> {
+        let mut chars: Vec<char> = san.chars().filter(|c| !"+#!?x=".contains(*c)).map(|c| if c == '0' {'O'} else {c}).collect();
+        let moves = self.legal_moves();
+        if chars.iter().all(|c| *c == 'O' || *c == '-') {
+            let kingside = match chars.iter().filter(|c| **c == 'O').count() {
+                2 => true,
+                3 => false,
+                _ => return None,
+            };
+            return moves.into_iter().find(|bmove| {
+                let ccheck = self.can_castle(bmove.from, bmove.to);
+                ccheck.cancastle && ccheck.rooksource.is_some_and(|rook| (rook.file() > bmove.from.file()) == kingside)
+            });
+        }
+        let piece_type = |c: char| match c {
+            'K' => Some(PieceType::King),
+            'Q' => Some(PieceType::Queen),
+            'R' => Some(PieceType::Rook),
+            'B' => Some(PieceType::Bishop),
+            'N' => Some(PieceType::Knight),
+            _ => None,
+        };
+        let promotion = if chars.len() > 2 {chars.last().and_then(|c| piece_type(*c))} else {None};
+        if promotion.is_some() {chars.pop();}
+        let piece = match chars.first().and_then(|c| piece_type(*c)) {
+            Some(piece) => {chars.remove(0); piece},
+            None => PieceType::Pawn,
+        };
+        if chars.len() < 2 {return None;}
+        let to: Square = chars[chars.len() - 2..].iter().collect::<String>().parse().ok()?;
+        //Whatever is left says which file or rank the piece came from.
+        let hints = &chars[..chars.len() - 2];
+        moves.into_iter().find(|bmove| {
+            bmove.to == to && bmove.promotion == promotion
+                && self[bmove.from].is_some_and(|moving| moving.piece == piece)
+                && hints.iter().all(|hint| File::from_char(*hint) == Some(bmove.from.file()) || Rank::from_char(*hint) == Some(bmove.from.rank()))
+        })
+    }
+
+    //A move in UCI notation if it can be played here, as it is listed in the legal moves. None for the null move.
+    pub fn parse_uci(&self, uci: &str) -> Result<Option<SimpleMove>, ParseError> {
+        let Some(bmove) = SimpleMove::from_uci(uci)? else {return Ok(None)};
+        self.legal_move(bmove).map(Some).ok_or_else(|| ParseError::Illegal(uci.to_string()))
+    }
+
+    //The legal move `bmove` stands for. Castling written as the king taking its rook becomes the king's own move, when
+    //that is how the legal moves list it.
+    pub fn legal_move(&self, bmove: SimpleMove) -> Option<SimpleMove> {
+        let moves = self.legal_moves_from(bmove.from);
+        if moves.contains(&bmove) {return Some(bmove);}
+        let ccheck = self.can_castle(bmove.from, bmove.to);
+        if !ccheck.cancastle || bmove.promotion.is_some() {return None;}
+        let castle = SimpleMove {from: bmove.from, to: ccheck.kingdestination?, promotion: None};
+        moves.contains(&castle).then_some(castle)
+    }
+
+    //Whether the color has enough material to ever checkmate, as used when the opponent flags.
+    //A lone minor piece can only mate if the opponent has something of their own to block with.
+    pub fn can_checkmate(&self, color: PieceColor) -> bool {
+        let mut minors = 0;
+        let mut bishop_squares = [false; 2];
+        let mut knights = 0;
+        let mut opponent_pieces = 0;
+        for square in Square::all() {
+            let Some(piece) = self[square] else {continue};
+            if piece.color != color {
+                if piece.piece != PieceType::King {opponent_pieces += 1;}
+                continue;
+            }
+            match piece.piece {
+                PieceType::Pawn | PieceType::Rook | PieceType::Queen => return true,
+                PieceType::Bishop => {
+                    minors += 1;
+                    bishop_squares[square.is_light() as usize] = true;
+                },
+                PieceType::Knight => {
+                    minors += 1;
+                    knights += 1;
+                },
+                PieceType::King => (),
+            }
+        }
+        match minors {
+            0 => false,
+            1 => opponent_pieces > 0,
+            //Bishops that all share a square color can't mate a bare king.
+            _ => knights > 0 || (bishop_squares[0] && bishop_squares[1]) || opponent_pieces > 0,
+        }
+    }
+
+    pub fn is_gameover(&self) -> GameOverState {
+        if let Some(outcome) = self.outcome {return outcome;}
+
+        if let Some(win) = self.variant_win() {return win;}
+
+        if self.any_legal_moves() {return GameOverState::Ongoing;}
+        
+        let moving_color = self.to_move();
+
+        if self.is_check(moving_color) {return GameOverState::Checkmate(!moving_color);}
+        GameOverState::Stalemate
+    }
+
+    //A win under the variant's own rules by whoever just moved.
+    pub fn variant_win(&self) -> Option<GameOverState> {
+        let color = self.movelist.last()?.color;
+        match self.variant {
+            Variant::Standard => None,
+            Variant::KingOfTheHill => {
+                let king = self.king_coords(color);
+                ([File::D, File::E].contains(&king.file()) && [Rank::FOURTH, Rank::FIFTH].contains(&king.rank())).then_some(GameOverState::KingOfTheHill(color))
+            },
+            Variant::ThreeCheck => (self.checks[color.index()] >= 3).then_some(GameOverState::ThreeCheck(color)),
+        }
+    }
+
+    //Draws that end the game without either side having to claim them.
+    pub fn draw_by_rule(&self) -> Option<GameOverState> {
+        if !self.can_checkmate(PieceColor::White) && !self.can_checkmate(PieceColor::Black) {
+            return Some(GameOverState::InsufficientMaterial);
+        }
+        if self.halfmoves >= 100 {return Some(GameOverState::FiftyMoves);}
+        if self.is_threefold_repetition() {return Some(GameOverState::Repetition);}
+        None
+    }
+
+    //Whether the current position has come up three times, counting the side to move, castling and en passant rights.
+    pub fn is_threefold_repetition(&self) -> bool {
+        //Positions can only repeat since the last capture or pawn move.
+        if self.halfmoves < 8 {return false;}
+        let key = |board: &Board| board.fen().split(' ').take(4).collect::<Vec<_>>().join(" ");
+        let current = key(self);
+        let mut position = self.start_position();
+        let mut count = usize::from(key(&position) == current);
+        for bmove in &self.movelist {
+            position.make_move(bmove.from, bmove.to, bmove.promotion.map(|piece| Piece{piece, color: bmove.color}));
+            if key(&position) == current {count += 1;}
+        }
+        count >= 3
+    }
+
+    pub fn any_legal_moves(&self) -> bool {
+        let moving_color = self.to_move();
+        Square::all().any(|from| {
+            self[from].is_some_and(|piece| piece.color == moving_color) && Square::all().any(|to| self.is_legal_move(from, to))
+        })
+    }
+    
+    pub fn to_move(&self) -> PieceColor {
+        if let Some(prevmove) = self.lastmove {
+            return !prevmove.color;
+        }
+        self.first_to_move
+    }
+
+    pub fn can_promote(&self, from: Square, to: Square) -> bool {
+        if let Some(piece) = self[from] {
+            if piece.piece != PieceType::Pawn {return false;}
+            let promotion_rank = match piece.color {
+                PieceColor::White => Rank::EIGHTH,
+                PieceColor::Black => Rank::FIRST,
+            };
+            if to.rank() != promotion_rank {return false;}
+            return self.is_legal_move(from, to);
+        }
+        false
+    }
+
+    pub fn make_move(&mut self, from: Square, to: Square, promote: Option<Piece>) {
+        if self.outcome.is_some() {return;}
+        if self.is_legal_move(from, to) {
+            if let Some(piece) = self[from] {
+                let ccheck = self.can_castle(from, to);
+
+                //Is capture? Asked before the board changes, as en passant lands on an empty square.
+                let en_passant = self.is_en_passant(from, to);
+                let captured = en_passant || (self[to].is_some() && !ccheck.cancastle);
+
+                if en_passant {
+                    //The pawn taken is the one just behind where ours lands.
+                    if let Some(taken) = to.offset(0, -piece.color.forward()) {self[taken] = None;}
+                }
+                //if self.is_check_after(piece.color, from, to) {return;}
+
+                //Handle promotion
+                let mut dest_piece = self[from].unwrap();
+                if let Some(promote_to) = promote {
+                    if self.can_promote(from, to) {dest_piece = promote_to;}
+                }
+
+                //Update halfmove count
+                if captured || piece.piece == PieceType::Pawn {
+                    self.halfmoves = 0;
+                } else {
+                    self.halfmoves += 1;
+                }
+
+                //Update fullmove count
+                if let PieceColor::Black = piece.color {
+                    self.fullmoves += 1;
+                }
+
+                //Update castle availability
+                if self.castles.any_castle(piece.color) && [PieceType::King,PieceType::Rook].contains(&piece.piece) {
+                    //Get rook starting squares for the specific side.
+                    let rooks = self.castles.rooks[piece.color.index()];
+                    let kingside_rook = Square::new(rooks[1], piece.color.home_rank());
+                    let queenside_rook = Square::new(rooks[0], piece.color.home_rank());
+
+                    //If king moves at all, no more castling on either side.
+                    if let PieceType::King = piece.piece {
+                        self.castles.set_availability(piece.color, true, false);
+                        self.castles.set_availability(piece.color, false, false);
+                    } else { //Otherwise if a rook moves from its starting position, no more castling on that side.
+                        if from == kingside_rook {
+                            self.castles.set_availability(piece.color, true, false);
+                        }
+                        if from == queenside_rook {
+                            self.castles.set_availability(piece.color, false, false);
+                        }
+                    }
+                }
+                
+                if let (Some(rook), Some(rook_dest), Some(king_dest)) = (ccheck.rooksource, ccheck.rookdestination, ccheck.kingdestination) {
+                    //In Chess960 the king and rook can land on each other's squares, so both are lifted first.
+                    let rook_piece = self[rook];
+                    self[from] = None;
+                    self[rook] = None;
+                    self[king_dest] = Some(piece);
+                    self[rook_dest] = rook_piece;
+                } else {
+                    self[to] = Some(dest_piece);
+                    self[from] = None;
+                }
+
+                if self.variant == Variant::ThreeCheck && self.is_check(!piece.color) {
+                    self.checks[piece.color.index()] += 1;
+                }
+
+                let promotion = if dest_piece.piece != piece.piece {Some(dest_piece.piece)} else {None};
+                let thismove = Move{from,to,color:piece.color,piece, capture:captured, promotion};
+                self.lastmove = Some(thismove);
+                self.movelist.push(thismove);
+            }
+        }
+    }
+
+    pub fn can_castle(&self, from: Square, to: Square) -> CastleCheck {
+        if let Some(piece) = self[from] {
+            if piece.piece != PieceType::King {
+                return CastleCheck::new(false);
+            }
+            let home = piece.color.home_rank();
+            if from != Square::new(self.castles.kings[piece.color.index()], home) || to.rank() != home {
+                return CastleCheck::new(false);
+            }
+            let rook_piece = Some(Piece{piece: PieceType::Rook, color: piece.color});
+
+            //The king castles by moving to its square two or more files away, or by being dropped on the rook.
+            //Chess960 needs the latter when the king moves one square or not at all.
+            let kingside = if self[to] == rook_piece {
+                to.file() > from.file()
+            } else if [File::C, File::G].contains(&to.file()) && to.file().distance(from.file()) >= 2 {
+                to.file() == File::G
+            } else {
+                return CastleCheck::new(false);
+            };
+
+            //If the king or rook has moved at all, castling is illegal.
+            if !self.castles.check_availability(piece.color, kingside) {
+                return CastleCheck::new(false);
+            }
+            let rook = Square::new(self.castles.rooks[piece.color.index()][kingside as usize], home);
+            if self[rook] != rook_piece || (self[to] == rook_piece && to != rook) {
+                return CastleCheck::new(false);
+            }
+            let (king_dest, rook_dest) = if kingside {
+                (Square::new(File::G, home), Square::new(File::F, home))
+            } else {
+                (Square::new(File::C, home), Square::new(File::D, home))
+            };
+
+            //Every square the king or rook passes over or lands on must be empty, apart from the two of them.
+            let span = |a: Square, b: Square| File::ALL.into_iter().filter(move |file| (a.file().min(b.file())..=a.file().max(b.file())).contains(file));
+            for file in span(from, king_dest).chain(span(rook, rook_dest)) {
+                if file == from.file() || file == rook.file() {continue;}
+                if self[Square::new(file, home)].is_some() {return CastleCheck::new(false);}
+            }
+
+            //We may not castle out of, through or into check.
+            for file in span(from, king_dest) {
+                if Square::all().any(|source| self.is_attacking(source, Square::new(file, home), Some(piece.color))) {
+                    return CastleCheck::new(false);
+                }
+            }
+
+            //The rook moving away can still uncover a check along the first rank.
+            let mut after = self.clone();
+            after[from] = None;
+            after[rook] = None;
+            after[king_dest] = Some(piece);
+            after[rook_dest] = rook_piece;
+            if after.is_check(piece.color) {return CastleCheck::new(false);}
+
+            //If all of the checks passed, then castling is legal.
+            return CastleCheck{cancastle: true, rooksource: Some(rook), rookdestination: Some(rook_dest), kingdestination: Some(king_dest)};
+        }
+        CastleCheck::new(false)
+    }
+
+    pub fn is_en_passant(&self, from: Square, to: Square) -> bool {
+        if let Some(lastmove) = self.lastmove {
+            if let Some(piece) = self[from] {
+                //Pawn must have been moved last turn
+                if lastmove.piece.piece != PieceType::Pawn {return false;}
+
+                //Other pawn must have moved two spaces last turn.
+                if lastmove.from.rank().distance(lastmove.to.rank()) != 2 {return false;}
+
+                //We should end up on the same file as the last moved pawn, but should start adjacent to it.
+                if from.file().distance(lastmove.to.file()) != 1 || to.file() != lastmove.to.file() {return false;}
+
+                //Our pawn should end up one past the other.
+                return lastmove.to.offset(0, piece.color.forward()) == Some(to);
+            }
+        }
+        false
+    }
+
+    pub fn is_legal_move(&self, from: Square, to: Square) -> bool {
+
+        if let Some(piece) = self[from] {
+            if let Some(lastmove) = self.lastmove {
+                if lastmove.color == piece.color {
+                    return false;
+                }
+            } else if piece.color != self.first_to_move {
+                return false;
+            }
+            match piece.piece {
+                //Pawns need special logic because they attack and move differently
+                PieceType::Pawn => {
+                    if self[to].is_some() {
+                        return if self.is_attacking(from, to, None) {self.final_move_validation(from,to)} else {false};
+                    }
+                    let (hori_dif, vert_dif) = from.delta(to);
+                    let forward = piece.color.forward();
+                    if (vert_dif == forward) && (hori_dif == 0) {return self.final_move_validation(from,to);}
+                    //Two squares from the starting rank, if nothing is in the way.
+                    if (from.rank() == piece.color.pawn_rank()) && (vert_dif == 2 * forward) && (hori_dif == 0)
+                        && from.offset(0, forward).is_some_and(|square| self[square].is_none()) {
+                        return self.final_move_validation(from,to);
+                    }
+                    //Only other legal move would be en passant
+                    if self.is_en_passant(from,to) {self.final_move_validation(from,to)} else {false}
+                },
+                //King needs castling logic
+                PieceType::King => {
+                    //Castling checks every square the king crosses itself.
+                    let ccheck = self.can_castle(from,to);
+                    if ccheck.cancastle {true}
+                    //If not castling, return true if we are attacking that square.
+                    else if self.is_attacking(from,to, None) {self.final_move_validation(from,to)} else {false}
+                }
+                //Everything else can only move to where they are attacking.
+                _ => if self.is_attacking(from,to, None) {self.final_move_validation(from,to)} else {false},
+            }
+        } else {
+            false
+        }
+    }
+
+    pub fn king_coords(&self, color: PieceColor) -> Square {
+        let king = Some(Piece{piece: PieceType::King, color});
+        Square::all().find(|square| self[*square] == king).unwrap_or(Square::new(File::A, Rank::FIRST))
+    }
+
+    pub fn final_move_validation(&self, from: Square, to: Square) -> bool {
+        let orig = self[from].unwrap();
+        if self.is_check_after(orig.color, from, to) {return false;}
+        true
+    }
+
+    pub fn is_check_after(&self, color: PieceColor, from: Square, to: Square) -> bool {
+        let orig = self[from].unwrap();
+        let mut copy = self.clone();
+        copy[to] = Some(orig);
+        copy[from] = None;
+        copy.is_check(color)
+    }
+
+    pub fn is_check(&self, color: PieceColor) -> bool{
+        let king_coords = self.king_coords(color);
+        Square::all().any(|source| self.is_attacking(source, king_coords, None))
+    }
+
+    pub fn is_attacking(&self, source: Square, target: Square, override_source_color: Option<PieceColor>) -> bool {
+        if source == target {
+            return false;
+        }
+        let Some(piece) = self[source] else {return false};
+        if let Some(target_piece) = self[target] {
+            if target_piece.color == piece.color {return false;}
+        }
+        if let Some(color) = override_source_color {
+            if piece.color == color {return false;}
+        }
+        let (hori_dif, vert_dif) = source.delta(target);
+        match piece.piece {
+            //Target square must simply be within 1 tile distance.
+            PieceType::King => hori_dif.abs() <= 1 && vert_dif.abs() <= 1,
+            //Target square must be in front (relative) of and diagonally adjacent to the pawn. Not considering en passant because this is for the purposes of check/checkmate.
+            PieceType::Pawn => vert_dif == piece.color.forward() && hori_dif.abs() == 1,
+            //Makes an L shape.
+            PieceType::Knight => (hori_dif.abs() == 2 && vert_dif.abs() == 1) || (hori_dif.abs() == 1 && vert_dif.abs() == 2),
+            //Target square must be on the same rank or file as the attacking rook, with nothing in between.
+            PieceType::Rook => (hori_dif == 0 || vert_dif == 0) && self.is_path_clear(source, target),
+            //The absolutes of the vertical and horizontal differences must be equal if the pieces share a diagonal.
+            PieceType::Bishop => hori_dif.abs() == vert_dif.abs() && self.is_path_clear(source, target),
+            PieceType::Queen => (hori_dif == 0 || vert_dif == 0 || hori_dif.abs() == vert_dif.abs()) && self.is_path_clear(source, target),
+        }
+    }
+
+    //Whether every square strictly between two on the same rank, file or diagonal is empty.
+    fn is_path_clear(&self, source: Square, target: Square) -> bool {
+        let (hori_dif, vert_dif) = source.delta(target);
+        let steps = hori_dif.abs().max(vert_dif.abs());
+        (1..steps).all(|i| source.offset(i * hori_dif.signum(), i * vert_dif.signum()).is_some_and(|square| self[square].is_none()))
+    }
+}
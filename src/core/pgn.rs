@@ -0,0 +1,170 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use super::board::{Board, GameOverState, PieceColor, Variant};
+
+//Result as written at the end of a PGN game, "*" while it is still going.
+pub fn result_token(outcome: GameOverState) -> &'static str {
+    match (outcome, outcome.winner()) {
+        (GameOverState::Ongoing, _) => "*",
+        (_, Some(PieceColor::White)) => "1-0",
+        (_, Some(PieceColor::Black)) => "0-1",
+        (_, None) => "1/2-1/2",
+    }
+}
+
+//Value of the Termination tag, None while the game is going.
+fn termination(outcome: GameOverState) -> Option<&'static str> {
+    match outcome {
+        GameOverState::Ongoing => None,
+        GameOverState::Timeout(_) | GameOverState::TimeoutVsInsufficientMaterial(_) => Some("time forfeit"),
+        GameOverState::Adjudication(_) => Some("adjudication"),
+        GameOverState::Forfeit(_) => Some("abandoned"),
+        //Resignations and agreed draws end the game as normally as a mate does.
+        _ => Some("normal"),
+    }
+}
+
+//Today's date as PGN writes it, e.g. "2023.11.05".
+fn today() -> String {
+    let days = SystemTime::now().duration_since(UNIX_EPOCH).map(|since| since.as_secs() / 86400).unwrap_or(0) as i64;
+    //Days since 1970 to a calendar date, from http://howardhinnant.github.io/date_algorithms.html
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let day_of_era = z - era * 146097;
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 {month_index + 3} else {month_index - 9};
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    format!("{:04}.{:02}.{:02}", year, month, day)
+}
+
+//The game as PGN, given the SAN of every move played and the names of the [white, black] players.
+pub fn write_pgn(board: &Board, san: &[String], players: [&str; 2], time_control: Option<&str>) -> String {
+    let outcome = board.is_gameover();
+    let result = result_token(outcome);
+    let mut out = String::new();
+    let mut tags = vec![
+        ("Event", "Casual game".to_string()),
+        ("Site", "?".to_string()),
+        ("Date", today()),
+        ("Round", "-".to_string()),
+        ("White", players[0].to_string()),
+        ("Black", players[1].to_string()),
+        ("Result", result.to_string()),
+    ];
+    if let Some(termination) = termination(outcome) {
+        tags.push(("Termination", termination.to_string()));
+    }
+    if let Some(control) = time_control {
+        tags.push(("TimeControl", control.to_string()));
+    }
+    let variant = match board.variant {
+        Variant::Standard if !board.castles.is_standard() => Some("Chess960"),
+        Variant::Standard => None,
+        Variant::KingOfTheHill => Some("King of the Hill"),
+        Variant::ThreeCheck => Some("Three-check"),
+    };
+    if let Some(variant) = variant {
+        tags.push(("Variant", variant.to_string()));
+    }
+    //Games that don't start from the standard position say where they did.
+    if let Some(fen) = &board.start_fen {
+        tags.push(("SetUp", "1".to_string()));
+        tags.push(("FEN", fen.clone()));
+    }
+    for (name, value) in tags {
+        out += &format!("[{} \"{}\"]\n", name, value.replace('\\', "\\\\").replace('"', "\\\""));
+    }
+    out.push('\n');
+
+    let mut tokens = Vec::new();
+    //A game started with Black to move opens with "1...".
+    let first = board.start_position();
+    let offset = usize::from(first.to_move() == PieceColor::Black);
+    for (ply, san) in san.iter().enumerate() {
+        let number = first.fullmoves + (ply + offset) / 2;
+        if (ply + offset).is_multiple_of(2) {
+            tokens.push(format!("{}.", number));
+        } else if ply == 0 {
+            tokens.push(format!("{}...", number));
+        }
+        tokens.push(san.clone());
+    }
+    tokens.push(result.to_string());
+    //Movetext lines are kept under 80 characters.
+    let mut line = String::new();
+    for token in tokens {
+        if !line.is_empty() && line.len() + token.len() + 1 > 79 {
+            out += &line;
+            out.push('\n');
+            line.clear();
+        }
+        if !line.is_empty() {line.push(' ');}
+        line += &token;
+    }
+    out += &line;
+    out.push('\n');
+    out
+}
+
+//The first game in a PGN, played out from its starting position. Only the main line is followed, and the result
+//is not taken on trust: the game can be carried on unless it is over on the board.
+pub fn read_pgn(text: &str) -> Result<Board, String> {
+    let mut fen = None;
+    let mut variant = Variant::Standard;
+    let mut movetext = String::new();
+    for line in text.lines() {
+        let line = line.trim();
+        if let Some(tag) = line.strip_prefix('[').and_then(|tag| tag.strip_suffix(']')) {
+            //A second set of tags starts the next game.
+            if !movetext.trim().is_empty() {break;}
+            let Some((name, value)) = tag.split_once(' ') else {continue};
+            let value = value.trim().trim_matches('"').replace("\\\"", "\"").replace("\\\\", "\\");
+            match name {
+                "FEN" => fen = Some(value),
+                "Variant" => variant = match value.to_lowercase().as_str() {
+                    "king of the hill" | "kingofthehill" => Variant::KingOfTheHill,
+                    "three-check" | "threecheck" => Variant::ThreeCheck,
+                    _ => Variant::Standard,
+                },
+                _ => (),
+            }
+        } else if !line.starts_with('%') {
+            //Escaped lines are for other programs.
+            movetext += line;
+            movetext.push('\n');
+        }
+    }
+    let mut board = match &fen {
+        Some(fen) => Board::from_fen(fen)?,
+        None => Board::new(),
+    };
+    board.variant = variant;
+
+    //Comments, variations and annotation glyphs are skipped.
+    let mut depth = 0;
+    let mut comment = None;
+    let mut cleaned = String::new();
+    for c in movetext.chars() {
+        match (comment, c) {
+            (Some('{'), '}') | (Some(';'), '\n') => comment = None,
+            (Some(_), _) => (),
+            (None, '{' | ';') => comment = Some(c),
+            (None, '(') => depth += 1,
+            (None, ')') => depth -= 1,
+            (None, _) if depth > 0 => (),
+            (None, _) => cleaned.push(c),
+        }
+    }
+    for token in cleaned.split_whitespace() {
+        if ["1-0", "0-1", "1/2-1/2", "*"].contains(&token) {break;}
+        //Move numbers can be written against the move, as in "1.e4".
+        let san = token.rsplit('.').next().unwrap_or(token);
+        if san.is_empty() || san.starts_with('$') {continue;}
+        let bmove = board.parse_san(san).ok_or(format!("illegal move {} at move {}", san, board.fullmoves))?;
+        board.make_simple_move(&bmove);
+    }
+    Ok(board)
+}
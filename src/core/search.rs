@@ -0,0 +1,94 @@
+use rand::seq::SliceRandom;
+
+use super::board::{Board, GameOverState, MATE_SCORE, PieceType, SimpleMove};
+use super::square::Square;
+
+//The engine that plays without Stockfish: a plain alpha-beta search over material.
+//Unlike Stockfish it knows the variant rules, since it asks the board whether the game is over.
+
+fn piece_value(piece: PieceType) -> i32 {
+    match piece {
+        PieceType::Pawn => 100,
+        PieceType::Knight => 320,
+        PieceType::Bishop => 330,
+        PieceType::Rook => 500,
+        PieceType::Queen => 900,
+        PieceType::King => 0,
+    }
+}
+
+//Material for the side to move, with a little extra for pieces nearer the center.
+fn evaluate(board: &Board) -> i32 {
+    let to_move = board.to_move();
+    let mut score = 0;
+    for square in Square::all() {
+        let Some(piece) = board[square] else {continue};
+        let (file, rank) = (square.file().index(), square.rank().index());
+        let center = 6 - (file.abs_diff(3).min(file.abs_diff(4)) + rank.abs_diff(3).min(rank.abs_diff(4))) as i32;
+        let value = piece_value(piece.piece) + if piece.piece == PieceType::King {0} else {center * 2};
+        score += if piece.color == to_move {value} else {-value};
+    }
+    score
+}
+
+//Captures of the most valuable pieces first, so the search cuts off sooner.
+fn order_moves(board: &Board, moves: &mut [SimpleMove]) {
+    moves.sort_by_key(|bmove| {
+        let victim = board[bmove.to].filter(|piece| board[bmove.from].is_some_and(|own| own.color != piece.color));
+        -(victim.map_or(0, |piece| piece_value(piece.piece)) + bmove.promotion.map_or(0, piece_value))
+    });
+}
+
+//Score for the side to move, searching `depth` plies ahead. `ply` is how far from the root this is, so nearer mates score higher.
+fn negamax(board: &Board, depth: u32, ply: i32, mut alpha: i32, beta: i32) -> i32 {
+    //Whoever just moved has won.
+    if board.variant_win().is_some() {return -(MATE_SCORE - ply);}
+    if depth == 0 {return evaluate(board);}
+    let mut moves = board.legal_moves();
+    if moves.is_empty() {
+        return if board.is_check(board.to_move()) {-(MATE_SCORE - ply)} else {0};
+    }
+    order_moves(board, &mut moves);
+    for bmove in moves {
+        let mut next = board.clone();
+        next.make_simple_move(&bmove);
+        let score = -negamax(&next, depth - 1, ply + 1, -beta, -alpha);
+        if score >= beta {return beta;}
+        alpha = alpha.max(score);
+    }
+    alpha
+}
+
+impl Board {
+    //The move the built-in engine would play, searching `depth` plies.
+    pub fn builtin_best_move(&self, depth: u32) -> Option<SimpleMove> {
+        if self.is_gameover() != GameOverState::Ongoing {return None;}
+        let mut moves = self.legal_moves();
+        //Shuffle first so equal moves aren't always played the same way.
+        moves.shuffle(&mut rand::thread_rng());
+        order_moves(self, &mut moves);
+        let mut best = None;
+        let mut alpha = -MATE_SCORE - 1;
+        for bmove in moves {
+            let mut next = self.clone();
+            next.make_simple_move(&bmove);
+            let score = -negamax(&next, depth.max(1) - 1, 1, -MATE_SCORE - 1, -alpha);
+            if best.is_none() || score > alpha {
+                alpha = score;
+                best = Some(bmove);
+            }
+        }
+        best
+    }
+
+    //The built-in engine's score for the side to move, searching `depth` plies, in the same units as engine_evaluation.
+    pub fn builtin_evaluation(&self, depth: u32) -> i32 {
+        negamax(self, depth, 0, -MATE_SCORE - 1, MATE_SCORE + 1)
+    }
+
+    pub fn builtin_move(&mut self, depth: u32) {
+        if let Some(bmove) = self.builtin_best_move(depth) {
+            self.make_simple_move(&bmove);
+        }
+    }
+}
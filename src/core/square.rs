@@ -0,0 +1,231 @@
+use std::{fmt, str::FromStr};
+
+//Files and ranks count from 0, so a is file 0 and the first rank is rank 0, but they are read and written as on the board.
+
+#[derive(Clone,Copy,PartialEq,Eq,PartialOrd,Ord,Hash,Debug)]
+pub struct File(u8);
+
+#[derive(Clone,Copy,PartialEq,Eq,PartialOrd,Ord,Hash,Debug)]
+pub struct Rank(u8);
+
+#[derive(Clone,Copy,PartialEq,Eq,Hash,Debug)]
+pub struct Square{
+    file: File,
+    rank: Rank,
+}
+
+#[derive(Clone,PartialEq,Debug)]
+pub struct ParseSquareError(String);
+
+impl fmt::Display for ParseSquareError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "'{}' is not a square", self.0)
+    }
+}
+
+impl std::error::Error for ParseSquareError {}
+
+impl File {
+    pub const A: File = File(0);
+    pub const B: File = File(1);
+    pub const C: File = File(2);
+    pub const D: File = File(3);
+    pub const E: File = File(4);
+    pub const F: File = File(5);
+    pub const G: File = File(6);
+    pub const H: File = File(7);
+    pub const ALL: [File; 8] = [File::A, File::B, File::C, File::D, File::E, File::F, File::G, File::H];
+
+    pub fn new(index: usize) -> Option<File> {
+        (index < 8).then_some(File(index as u8))
+    }
+
+    pub fn index(self) -> usize {
+        self.0 as usize
+    }
+
+    //The file this many files towards h, None off the board.
+    pub fn offset(self, by: i32) -> Option<File> {
+        File::new(usize::try_from(self.0 as i32 + by).ok()?)
+    }
+
+    pub fn distance(self, other: File) -> usize {
+        self.0.abs_diff(other.0) as usize
+    }
+
+    pub fn from_char(c: char) -> Option<File> {
+        ('a'..='h').contains(&c).then(|| File(c as u8 - b'a'))
+    }
+
+    pub fn to_char(self) -> char {
+        (b'a' + self.0) as char
+    }
+}
+
+impl Rank {
+    pub const FIRST: Rank = Rank(0);
+    pub const SECOND: Rank = Rank(1);
+    pub const THIRD: Rank = Rank(2);
+    pub const FOURTH: Rank = Rank(3);
+    pub const FIFTH: Rank = Rank(4);
+    pub const SIXTH: Rank = Rank(5);
+    pub const SEVENTH: Rank = Rank(6);
+    pub const EIGHTH: Rank = Rank(7);
+    pub const ALL: [Rank; 8] = [Rank::FIRST, Rank::SECOND, Rank::THIRD, Rank::FOURTH, Rank::FIFTH, Rank::SIXTH, Rank::SEVENTH, Rank::EIGHTH];
+
+    pub fn new(index: usize) -> Option<Rank> {
+        (index < 8).then_some(Rank(index as u8))
+    }
+
+    pub fn index(self) -> usize {
+        self.0 as usize
+    }
+
+    //The rank this many ranks towards the eighth, None off the board.
+    pub fn offset(self, by: i32) -> Option<Rank> {
+        Rank::new(usize::try_from(self.0 as i32 + by).ok()?)
+    }
+
+    pub fn distance(self, other: Rank) -> usize {
+        self.0.abs_diff(other.0) as usize
+    }
+
+    pub fn from_char(c: char) -> Option<Rank> {
+        ('1'..='8').contains(&c).then(|| Rank(c as u8 - b'1'))
+    }
+
+    pub fn to_char(self) -> char {
+        (b'1' + self.0) as char
+    }
+}
+
+impl Square {
+    pub fn new(file: File, rank: Rank) -> Square {
+        Square {file, rank}
+    }
+
+    pub fn file(self) -> File {
+        self.file
+    }
+
+    pub fn rank(self) -> Rank {
+        self.rank
+    }
+
+    //Number of the square in the order of all(), a1 is 0 and h8 is 63.
+    pub fn index(self) -> usize {
+        self.rank.index() * 8 + self.file.index()
+    }
+
+    //Every square, a1 to h1 and up to h8.
+    pub fn all() -> impl Iterator<Item = Square> {
+        Rank::ALL.into_iter().flat_map(|rank| File::ALL.into_iter().map(move |file| Square {file, rank}))
+    }
+
+    //The square this many files towards h and ranks towards the eighth, None off the board.
+    pub fn offset(self, files: i32, ranks: i32) -> Option<Square> {
+        Some(Square {file: self.file.offset(files)?, rank: self.rank.offset(ranks)?})
+    }
+
+    //Files towards h and ranks towards the eighth to get from here to `other`.
+    pub fn delta(self, other: Square) -> (i32, i32) {
+        (other.file.0 as i32 - self.file.0 as i32, other.rank.0 as i32 - self.rank.0 as i32)
+    }
+
+    pub fn is_light(self) -> bool {
+        !(self.file.0 + self.rank.0).is_multiple_of(2)
+    }
+}
+
+impl fmt::Display for File {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.to_char())
+    }
+}
+
+impl fmt::Display for Rank {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.to_char())
+    }
+}
+
+impl fmt::Display for Square {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}{}", self.file, self.rank)
+    }
+}
+
+//A single letter from a to h.
+impl FromStr for File {
+    type Err = ParseSquareError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut chars = s.chars();
+        match (chars.next().and_then(File::from_char), chars.next()) {
+            (Some(file), None) => Ok(file),
+            _ => Err(ParseSquareError(s.to_string())),
+        }
+    }
+}
+
+//A single digit from 1 to 8.
+impl FromStr for Rank {
+    type Err = ParseSquareError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut chars = s.chars();
+        match (chars.next().and_then(Rank::from_char), chars.next()) {
+            (Some(rank), None) => Ok(rank),
+            _ => Err(ParseSquareError(s.to_string())),
+        }
+    }
+}
+
+//A square such as "e4".
+impl FromStr for Square {
+    type Err = ParseSquareError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut chars = s.chars();
+        match (chars.next().and_then(File::from_char), chars.next().and_then(Rank::from_char), chars.next()) {
+            (Some(file), Some(rank), None) => Ok(Square {file, rank}),
+            _ => Err(ParseSquareError(s.to_string())),
+        }
+    }
+}
+
+//All three are written by name, so a square is "e4" rather than a pair of numbers.
+#[cfg(feature = "serde")]
+mod serialize {
+    use std::{fmt::Display, str::FromStr};
+
+    use serde::{Deserialize, Deserializer, Serialize, Serializer, de::Error};
+
+    use super::{File, Rank, Square};
+
+    fn serialize<T: Display, S: Serializer>(value: &T, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(value)
+    }
+
+    fn deserialize<'de, T: FromStr<Err = super::ParseSquareError>, D: Deserializer<'de>>(deserializer: D) -> Result<T, D::Error> {
+        String::deserialize(deserializer)?.parse().map_err(D::Error::custom)
+    }
+
+    macro_rules! by_name {
+        ($($kind:ty),*) => {$(
+            impl Serialize for $kind {
+                fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                    serialize(self, serializer)
+                }
+            }
+
+            impl<'de> Deserialize<'de> for $kind {
+                fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                    deserialize(deserializer)
+                }
+            }
+        )*};
+    }
+
+    by_name!(File, Rank, Square);
+}
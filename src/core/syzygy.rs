@@ -0,0 +1,1016 @@
+use std::{collections::HashMap, fs::{self, File}, io, path::{Path, PathBuf}, sync::{Arc, OnceLock}};
+
+use memmap2::Mmap;
+
+use super::board::{Board, GameOverState, Piece, PieceColor, PieceType, SimpleMove};
+use super::square::Square;
+
+//Port of the Syzygy prober found in stockfish/src/syzygy/tbprobe.cpp, working on our own Board.
+
+const TBPIECES: usize = 7;
+const MAX_DTZ: i32 = 1 << 18;
+
+const WDL_MAGIC: [u8; 4] = [0x71, 0xE8, 0x23, 0x5D];
+const DTZ_MAGIC: [u8; 4] = [0xD7, 0x66, 0x0C, 0xA5];
+
+//Table flags. All of them refer to DTZ tables, the last one to WDL tables too.
+const FLAG_STM: u8 = 1;
+const FLAG_MAPPED: u8 = 2;
+const FLAG_WIN_PLIES: u8 = 4;
+const FLAG_LOSS_PLIES: u8 = 8;
+const FLAG_WIDE: u8 = 16;
+const FLAG_SINGLE_VALUE: u8 = 128;
+
+#[derive(Clone)]
+pub struct TablebaseSettings{
+    //Directories containing .rtbw and .rtbz files, separated by ':' (';' on Windows).
+    pub path: String,
+    //End the game as soon as the tablebase knows the result.
+    pub adjudicate: bool,
+}
+
+#[derive(Clone,Copy,PartialEq,Eq,PartialOrd,Ord,Debug)]
+pub enum Wdl{
+    Loss = -2,
+    //Loss, but drawn under the 50-move rule.
+    BlessedLoss = -1,
+    Draw = 0,
+    //Win, but drawn under the 50-move rule.
+    CursedWin = 1,
+    Win = 2,
+}
+
+impl Wdl {
+    fn from_i32(value: i32) -> Self {
+        match value {
+            -2 => Wdl::Loss,
+            -1 => Wdl::BlessedLoss,
+            1 => Wdl::CursedWin,
+            2 => Wdl::Win,
+            _ => Wdl::Draw,
+        }
+    }
+}
+
+#[derive(Clone,Copy,PartialEq,Debug)]
+enum ProbeState{
+    Ok,
+    //DTZ table only stores the other side to move.
+    ChangeStm,
+    //Best move zeroes the 50-move counter, so the DTZ table cannot be trusted.
+    ZeroingBestMove,
+}
+
+#[derive(Clone,Copy,PartialEq)]
+enum TableKind{
+    Wdl,
+    Dtz,
+}
+
+struct Lookups {
+    map_pawns: [usize; 64],
+    map_b1h1h7: [usize; 64],
+    map_a1d1d4: [usize; 64],
+    map_kk: [[usize; 64]; 10],
+    binomial: [[u64; 64]; 6],
+    lead_pawn_idx: [[u64; 64]; 6],
+    lead_pawns_size: [[u64; 4]; 6],
+}
+
+fn off_a1h8(sq: usize) -> i32 {
+    (sq >> 3) as i32 - (sq & 7) as i32
+}
+
+fn lookups() -> &'static Lookups {
+    static LOOKUPS: OnceLock<Lookups> = OnceLock::new();
+    LOOKUPS.get_or_init(|| {
+        let mut l = Lookups {
+            map_pawns: [0; 64],
+            map_b1h1h7: [0; 64],
+            map_a1d1d4: [0; 64],
+            map_kk: [[0; 64]; 10],
+            binomial: [[0; 64]; 6],
+            lead_pawn_idx: [[0; 64]; 6],
+            lead_pawns_size: [[0; 4]; 6],
+        };
+
+        //map_b1h1h7 encodes a square below the a1-h8 diagonal to 0..27.
+        let mut code = 0;
+        for sq in 0..64 {
+            if off_a1h8(sq) < 0 {
+                l.map_b1h1h7[sq] = code;
+                code += 1;
+            }
+        }
+
+        //map_a1d1d4 encodes a square in the a1-d1-d4 triangle to 0..9, diagonal squares last.
+        let mut diagonal = Vec::new();
+        code = 0;
+        for sq in 0..28 {
+            if off_a1h8(sq) < 0 && (sq & 7) <= 3 {
+                l.map_a1d1d4[sq] = code;
+                code += 1;
+            } else if off_a1h8(sq) == 0 && (sq & 7) <= 3 {
+                diagonal.push(sq);
+            }
+        }
+        for sq in diagonal {
+            l.map_a1d1d4[sq] = code;
+            code += 1;
+        }
+
+        //map_kk encodes the 462 legal placements of two kings with the first in the a1-d1-d4 triangle.
+        let mut both_on_diagonal = Vec::new();
+        code = 0;
+        for idx in 0..10 {
+            for s1 in 0..28 {
+                if l.map_a1d1d4[s1] != idx || (idx == 0 && s1 != 1) {continue;}
+                for s2 in 0..64 {
+                    let rank_dif = ((s1 >> 3) as i32 - (s2 >> 3) as i32).abs();
+                    let file_dif = ((s1 & 7) as i32 - (s2 & 7) as i32).abs();
+                    //Skip kings next to each other, and the second above the diagonal when the first is on it.
+                    if (rank_dif <= 1 && file_dif <= 1) || (off_a1h8(s1) == 0 && off_a1h8(s2) > 0) {
+                        continue;
+                    } else if off_a1h8(s1) == 0 && off_a1h8(s2) == 0 {
+                        both_on_diagonal.push((idx, s2));
+                    } else {
+                        l.map_kk[idx][s2] = code;
+                        code += 1;
+                    }
+                }
+            }
+        }
+        for (idx, s2) in both_on_diagonal {
+            l.map_kk[idx][s2] = code;
+            code += 1;
+        }
+
+        //binomial[k][n] is the number of ways to choose k elements from a set of n.
+        l.binomial[0][0] = 1;
+        for n in 1..64 {
+            for k in 0..6 {
+                if k > n {break;}
+                l.binomial[k][n] = (if k > 0 {l.binomial[k - 1][n - 1]} else {0})
+                    + (if k < n {l.binomial[k][n - 1]} else {0});
+            }
+        }
+
+        //map_pawns encodes a2-h7 to 0..47, the highest value belonging to the leading pawn.
+        let mut available_squares = 47;
+        for lead_pawns_cnt in 1..6 {
+            for file in 0..4 {
+                let mut idx = 0;
+                for rank in 1..7 {
+                    let sq = rank * 8 + file;
+                    if lead_pawns_cnt == 1 {
+                        l.map_pawns[sq] = available_squares;
+                        available_squares -= 1;
+                        l.map_pawns[sq ^ 7] = available_squares;
+                        available_squares = available_squares.saturating_sub(1);
+                    }
+                    l.lead_pawn_idx[lead_pawns_cnt][sq] = idx;
+                    idx += l.binomial[lead_pawns_cnt - 1][l.map_pawns[sq]];
+                }
+                l.lead_pawns_size[lead_pawns_cnt][file] = idx;
+            }
+        }
+        l
+    })
+}
+
+fn read_u16_le(data: &[u8], offset: usize) -> u16 {
+    u16::from_le_bytes([data[offset], data[offset + 1]])
+}
+
+fn read_u32_le(data: &[u8], offset: usize) -> u32 {
+    u32::from_le_bytes(data[offset..offset + 4].try_into().unwrap())
+}
+
+fn read_u32_be(data: &[u8], offset: usize) -> u32 {
+    u32::from_be_bytes(data[offset..offset + 4].try_into().unwrap())
+}
+
+fn read_u64_be(data: &[u8], offset: usize) -> u64 {
+    u64::from_be_bytes(data[offset..offset + 8].try_into().unwrap())
+}
+
+//Low level indexing information for one sub-table. Offsets point into the mapped file.
+#[derive(Clone,Default)]
+struct PairsData {
+    flags: u8,
+    max_sym_len: u8,
+    min_sym_len: u8,
+    blocks_num: u32,
+    sizeof_block: usize,
+    span: usize,
+    lowest_sym: usize,
+    btree: usize,
+    block_length: usize,
+    block_length_size: u32,
+    sparse_index: usize,
+    sparse_index_size: usize,
+    data: usize,
+    base64: Vec<u64>,
+    symlen: Vec<u8>,
+    pieces: [u8; TBPIECES],
+    group_idx: [u64; TBPIECES + 1],
+    group_len: [usize; TBPIECES + 1],
+    map_idx: [u16; 4],
+}
+
+impl PairsData {
+    fn btree_left(&self, data: &[u8], sym: usize) -> usize {
+        let lr = self.btree + 3 * sym;
+        (((data[lr + 1] & 0xF) as usize) << 8) | data[lr] as usize
+    }
+
+    fn btree_right(&self, data: &[u8], sym: usize) -> usize {
+        let lr = self.btree + 3 * sym;
+        ((data[lr + 2] as usize) << 4) | (data[lr + 1] >> 4) as usize
+    }
+
+    fn set_symlen(&mut self, data: &[u8], sym: usize, visited: &mut [bool]) -> u8 {
+        visited[sym] = true;
+        let right = self.btree_right(data, sym);
+        if right == 0xFFF {return 0;}
+        let left = self.btree_left(data, sym);
+        if !visited[left] {
+            self.symlen[left] = self.set_symlen(data, left, visited);
+        }
+        if !visited[right] {
+            self.symlen[right] = self.set_symlen(data, right, visited);
+        }
+        self.symlen[left].wrapping_add(self.symlen[right]).wrapping_add(1)
+    }
+
+    fn set_sizes(&mut self, data: &[u8], mut offset: usize) -> usize {
+        self.flags = data[offset];
+        offset += 1;
+
+        if self.flags & FLAG_SINGLE_VALUE != 0 {
+            //The single value is stored where the minimum symbol length would be.
+            self.min_sym_len = data[offset];
+            return offset + 1;
+        }
+
+        let groups = self.group_len.iter().position(|len| *len == 0).unwrap_or(TBPIECES);
+        let tb_size = self.group_idx[groups];
+
+        self.sizeof_block = 1usize << data[offset];
+        self.span = 1usize << data[offset + 1];
+        self.sparse_index_size = tb_size.div_ceil(self.span as u64) as usize;
+        let padding = data[offset + 2] as u32;
+        self.blocks_num = read_u32_le(data, offset + 3);
+        self.block_length_size = self.blocks_num + padding;
+        self.max_sym_len = data[offset + 7];
+        self.min_sym_len = data[offset + 8];
+        offset += 9;
+        self.lowest_sym = offset;
+
+        let lengths = (self.max_sym_len - self.min_sym_len) as usize + 1;
+        self.base64 = vec![0; lengths];
+        for i in (0..lengths - 1).rev() {
+            let lowest = read_u16_le(data, self.lowest_sym + 2 * i) as u64;
+            let next_lowest = read_u16_le(data, self.lowest_sym + 2 * (i + 1)) as u64;
+            self.base64[i] = (self.base64[i + 1] + lowest - next_lowest) / 2;
+        }
+        for i in 0..lengths {
+            self.base64[i] <<= 64 - i - self.min_sym_len as usize;
+        }
+
+        offset += lengths * 2;
+        let symbols = read_u16_le(data, offset) as usize;
+        offset += 2;
+        self.symlen = vec![0; symbols];
+        self.btree = offset;
+
+        let mut visited = vec![false; symbols];
+        for sym in 0..symbols {
+            if !visited[sym] {
+                self.symlen[sym] = self.set_symlen(data, sym, &mut visited);
+            }
+        }
+
+        offset + symbols * 3 + (symbols & 1)
+    }
+
+    //Decode the value at the given index from the Huffman compressed blocks.
+    fn decompress_pairs(&self, data: &[u8], idx: u64) -> i32 {
+        if self.flags & FLAG_SINGLE_VALUE != 0 {
+            return self.min_sym_len as i32;
+        }
+
+        let span = self.span as u64;
+        let k = (idx / span) as usize;
+        let mut block = read_u32_le(data, self.sparse_index + 6 * k) as usize;
+        let mut offset = read_u16_le(data, self.sparse_index + 6 * k + 4) as i64;
+        offset += (idx % span) as i64 - (span / 2) as i64;
+
+        let block_length = |block: usize| read_u16_le(data, self.block_length + 2 * block) as i64;
+        while offset < 0 {
+            block -= 1;
+            offset += block_length(block) + 1;
+        }
+        while offset > block_length(block) {
+            offset -= block_length(block) + 1;
+            block += 1;
+        }
+
+        let mut ptr = self.data + block * self.sizeof_block;
+        let mut buf64 = read_u64_be(data, ptr);
+        ptr += 8;
+        let mut buf64_size = 64;
+        let mut sym;
+
+        loop {
+            let mut len = 0;
+            while buf64 < self.base64[len] {
+                len += 1;
+            }
+            sym = ((buf64 - self.base64[len]) >> (64 - len - self.min_sym_len as usize)) as usize;
+            sym += read_u16_le(data, self.lowest_sym + 2 * len) as usize;
+
+            if offset < self.symlen[sym] as i64 + 1 {break;}
+
+            offset -= self.symlen[sym] as i64 + 1;
+            len += self.min_sym_len as usize;
+            buf64 <<= len;
+            buf64_size -= len;
+
+            if buf64_size <= 32 {
+                buf64_size += 32;
+                buf64 |= (read_u32_be(data, ptr) as u64) << (64 - buf64_size);
+                ptr += 4;
+            }
+        }
+
+        //Expand the pair symbols until reaching the leaf holding our value.
+        while self.symlen[sym] != 0 {
+            let left = self.btree_left(data, sym);
+            if offset < self.symlen[left] as i64 + 1 {
+                sym = left;
+            } else {
+                offset -= self.symlen[left] as i64 + 1;
+                sym = self.btree_right(data, sym);
+            }
+        }
+
+        self.btree_left(data, sym) as i32
+    }
+}
+
+struct TableData {
+    mmap: Mmap,
+    //[wtm / btm][file a..d, or only the first when there are no pawns]
+    items: [[PairsData; 4]; 2],
+    map: usize,
+}
+
+struct Table {
+    kind: TableKind,
+    path: PathBuf,
+    piece_count: usize,
+    has_pawns: bool,
+    has_unique_pieces: bool,
+    //[lead color / other color]
+    pawn_count: [usize; 2],
+    //Both sides have the same material, so only white to move is stored.
+    symmetric: bool,
+    //Filled in when the tablebase is opened.
+    data: OnceLock<TableData>,
+}
+
+impl Table {
+    fn new(name: &str, kind: TableKind, path: PathBuf) -> Self {
+        let (white, black) = name.split_once('v').unwrap_or((name, ""));
+        let count = |side: &str, c: char| side.chars().filter(|x| *x == c).count();
+        let has_unique_pieces = ['Q', 'R', 'B', 'N', 'P'].iter().any(|c| count(white, *c) == 1 || count(black, *c) == 1);
+        let white_pawns = count(white, 'P');
+        let black_pawns = count(black, 'P');
+        //The leading color is the side with less pawns, as that compresses better.
+        let lead_white = black_pawns == 0 || (white_pawns > 0 && black_pawns >= white_pawns);
+        Table {
+            kind,
+            path,
+            piece_count: white.len() + black.len(),
+            has_pawns: white_pawns + black_pawns > 0,
+            has_unique_pieces,
+            pawn_count: if lead_white {[white_pawns, black_pawns]} else {[black_pawns, white_pawns]},
+            symmetric: white == black,
+            data: OnceLock::new(),
+        }
+    }
+
+    fn sides(&self) -> usize {
+        if self.kind == TableKind::Wdl && !self.symmetric {2} else {1}
+    }
+
+    fn get<'a>(&self, data: &'a TableData, stm: usize, file: usize) -> &'a PairsData {
+        &data.items[stm % self.sides()][if self.has_pawns {file} else {0}]
+    }
+
+    fn mapped(&self) -> Option<&TableData> {
+        self.data.get()
+    }
+
+    fn load(&self) -> io::Result<()> {
+        let data = self.map_file().map_err(|e| io::Error::new(e.kind(), format!("{}: {}", self.path.display(), e)))?;
+        let _ = self.data.set(data);
+        Ok(())
+    }
+
+    fn map_file(&self) -> io::Result<TableData> {
+        let file = File::open(&self.path)?;
+        //Safety: tablebase files are read only and are not expected to change while mapped.
+        let mmap = unsafe { Mmap::map(&file)? };
+        let magic = if let TableKind::Wdl = self.kind {WDL_MAGIC} else {DTZ_MAGIC};
+        if mmap.len() % 64 != 16 || mmap[0..4] != magic {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "corrupt tablebase file"));
+        }
+        let mut data = TableData {
+            mmap,
+            items: Default::default(),
+            map: 0,
+        };
+        self.init(&mut data);
+        Ok(data)
+    }
+
+    fn set_groups(&self, d: &mut PairsData, order: [usize; 2], file: usize) {
+        let l = lookups();
+        let mut n = 0;
+        let mut first_len: i32 = if self.has_pawns {0} else if self.has_unique_pieces {3} else {2};
+        d.group_len[n] = 1;
+
+        //Pieces of the same type and color form a group, except for the leading group.
+        for i in 1..self.piece_count {
+            first_len -= 1;
+            if first_len > 0 || d.pieces[i] == d.pieces[i - 1] {
+                d.group_len[n] += 1;
+            } else {
+                n += 1;
+                d.group_len[n] = 1;
+            }
+        }
+        n += 1;
+        d.group_len[n] = 0;
+
+        let pp = self.has_pawns && self.pawn_count[1] > 0;
+        let mut next = if pp {2} else {1};
+        let mut free_squares = 64 - d.group_len[0] - if pp {d.group_len[1]} else {0};
+        let mut idx = 1u64;
+
+        let mut k = 0;
+        while next < n || k == order[0] || k == order[1] {
+            if k == order[0] {
+                d.group_idx[0] = idx;
+                idx *= if self.has_pawns {l.lead_pawns_size[d.group_len[0]][file]} else if self.has_unique_pieces {31332} else {462};
+            } else if k == order[1] {
+                d.group_idx[1] = idx;
+                idx *= l.binomial[d.group_len[1]][48 - d.group_len[0]];
+            } else {
+                d.group_idx[next] = idx;
+                idx *= l.binomial[d.group_len[next]][free_squares];
+                free_squares -= d.group_len[next];
+                next += 1;
+            }
+            k += 1;
+        }
+        d.group_idx[n] = idx;
+    }
+
+    fn init(&self, table: &mut TableData) {
+        let data: &[u8] = &table.mmap;
+        let mut offset = 5;
+        let sides = self.sides();
+        let max_file = if self.has_pawns {3} else {0};
+        let pp = self.has_pawns && self.pawn_count[1] > 0;
+
+        for file in 0..=max_file {
+            let order = [
+                [(data[offset] & 0xF) as usize, if pp {(data[offset + 1] & 0xF) as usize} else {0xF}],
+                [(data[offset] >> 4) as usize, if pp {(data[offset + 1] >> 4) as usize} else {0xF}],
+            ];
+            offset += 1 + pp as usize;
+            for k in 0..self.piece_count {
+                for i in 0..sides {
+                    table.items[i][file].pieces[k] = if i == 1 {data[offset] >> 4} else {data[offset] & 0xF};
+                }
+                offset += 1;
+            }
+            for (i, order) in order.into_iter().enumerate().take(sides) {
+                let mut d = std::mem::take(&mut table.items[i][file]);
+                self.set_groups(&mut d, order, file);
+                table.items[i][file] = d;
+            }
+        }
+        offset += offset & 1;
+
+        for file in 0..=max_file {
+            for i in 0..sides {
+                offset = table.items[i][file].set_sizes(data, offset);
+            }
+        }
+
+        if let TableKind::Dtz = self.kind {
+            table.map = offset;
+            for file in 0..=max_file {
+                let d = &mut table.items[0][file];
+                if d.flags & FLAG_MAPPED == 0 {continue;}
+                if d.flags & FLAG_WIDE != 0 {
+                    offset += offset & 1;
+                    for i in 0..4 {
+                        d.map_idx[i] = ((offset - table.map) / 2 + 1) as u16;
+                        offset += 2 * read_u16_le(data, offset) as usize + 2;
+                    }
+                } else {
+                    for i in 0..4 {
+                        d.map_idx[i] = (offset - table.map + 1) as u16;
+                        offset += data[offset] as usize + 1;
+                    }
+                }
+            }
+            offset += offset & 1;
+        }
+
+        for file in 0..=max_file {
+            for i in 0..sides {
+                let d = &mut table.items[i][file];
+                d.sparse_index = offset;
+                offset += d.sparse_index_size * 6;
+            }
+        }
+        for file in 0..=max_file {
+            for i in 0..sides {
+                let d = &mut table.items[i][file];
+                d.block_length = offset;
+                offset += d.block_length_size as usize * 2;
+            }
+        }
+        for file in 0..=max_file {
+            for i in 0..sides {
+                let d = &mut table.items[i][file];
+                offset = (offset + 0x3F) & !0x3F;
+                d.data = offset;
+                offset += d.blocks_num as usize * d.sizeof_block;
+            }
+        }
+    }
+
+    //DTZ scores are stored remapped by frequency, and in moves rather than plies for some tables.
+    fn map_score(&self, table: &TableData, file: usize, value: i32, wdl: i32) -> i32 {
+        if let TableKind::Wdl = self.kind {
+            return value - 2;
+        }
+        const WDL_MAP: [usize; 5] = [1, 3, 0, 2, 0];
+        let d = self.get(table, 0, file);
+        let data: &[u8] = &table.mmap;
+        let mut value = value;
+        if d.flags & FLAG_MAPPED != 0 {
+            let index = d.map_idx[WDL_MAP[(wdl + 2) as usize]] as usize + value as usize;
+            value = if d.flags & FLAG_WIDE != 0 {
+                read_u16_le(data, table.map + 2 * index) as i32
+            } else {
+                data[table.map + index] as i32
+            };
+        }
+        if (wdl == 2 && d.flags & FLAG_WIN_PLIES == 0)
+            || (wdl == -2 && d.flags & FLAG_LOSS_PLIES == 0)
+            || wdl == 1
+            || wdl == -1 {
+            value *= 2;
+        }
+        value + 1
+    }
+}
+
+fn tb_piece(piece: Piece) -> u8 {
+    let kind = match piece.piece {
+        PieceType::Pawn => 1,
+        PieceType::Knight => 2,
+        PieceType::Bishop => 3,
+        PieceType::Rook => 4,
+        PieceType::Queen => 5,
+        PieceType::King => 6,
+    };
+    if let PieceColor::Black = piece.color {kind + 8} else {kind}
+}
+
+fn material(board: &Board, color: PieceColor) -> String {
+    let mut out = String::new();
+    for (kind, c) in [(PieceType::King, 'K'), (PieceType::Queen, 'Q'), (PieceType::Rook, 'R'), (PieceType::Bishop, 'B'), (PieceType::Knight, 'N'), (PieceType::Pawn, 'P')] {
+        for rank in board.tiles.iter() {
+            for piece in rank.iter().flatten() {
+                if piece.color == color && piece.piece == kind {out.push(c);}
+            }
+        }
+    }
+    out
+}
+
+fn is_zeroing(board: &Board, bmove: &SimpleMove) -> bool {
+    let Some(piece) = board[bmove.from] else {return false};
+    piece.piece == PieceType::Pawn || board[bmove.to].is_some()
+}
+
+fn is_capture(board: &Board, bmove: &SimpleMove) -> bool {
+    if board[bmove.to].is_some() {return true;}
+    let Some(piece) = board[bmove.from] else {return false};
+    piece.piece == PieceType::Pawn && board.is_en_passant(bmove.from, bmove.to)
+}
+
+fn after(board: &Board, bmove: &SimpleMove) -> Board {
+    let mut copy = board.clone();
+    copy.make_simple_move(bmove);
+    copy
+}
+
+fn dtz_before_zeroing(wdl: i32) -> i32 {
+    match wdl {
+        2 => 1,
+        1 => 101,
+        -1 => -101,
+        -2 => -1,
+        _ => 0,
+    }
+}
+
+pub struct Tablebase {
+    //Keyed by material as in the file names, e.g. "KRvK".
+    tables: HashMap<String, (Table, Option<Table>)>,
+    max_pieces: usize,
+}
+
+impl Tablebase {
+    pub fn open(paths: &str) -> io::Result<Self> {
+        let separator = if cfg!(windows) {';'} else {':'};
+        let mut wdl = HashMap::new();
+        let mut dtz = HashMap::new();
+        for dir in paths.split(separator).filter(|dir| !dir.is_empty()) {
+            for entry in fs::read_dir(dir)? {
+                let path = entry?.path();
+                let (Some(stem), Some(extension)) = (path.file_stem().and_then(|x| x.to_str()), path.extension().and_then(|x| x.to_str())) else {continue};
+                if !stem.starts_with('K') || !stem.contains('v') || stem.len() > TBPIECES + 1 {continue;}
+                match extension {
+                    "rtbw" => {wdl.entry(stem.to_string()).or_insert(path.clone());},
+                    "rtbz" => {dtz.entry(stem.to_string()).or_insert(path.clone());},
+                    _ => continue,
+                }
+            }
+        }
+
+        let mut tables = HashMap::new();
+        let mut max_pieces = 0;
+        for (name, path) in wdl {
+            let dtz_table = dtz.remove(&name).map(|dtz_path| Table::new(&name, TableKind::Dtz, dtz_path));
+            let table = Table::new(&name, TableKind::Wdl, path);
+            table.load()?;
+            if let Some(dtz_table) = &dtz_table {
+                dtz_table.load()?;
+            }
+            max_pieces = max_pieces.max(table.piece_count);
+            tables.insert(name, (table, dtz_table));
+        }
+        Ok(Tablebase {tables, max_pieces})
+    }
+
+    pub fn max_pieces(&self) -> usize {
+        self.max_pieces
+    }
+
+    pub fn can_probe(&self, board: &Board) -> bool {
+        let pieces = board.tiles.iter().flatten().flatten().count();
+        if pieces > self.max_pieces || pieces > TBPIECES {return false;}
+        //Tables don't cover positions where castling is still possible, wherever the king and rooks started.
+        !board.castles.any_castle(PieceColor::White) && !board.castles.any_castle(PieceColor::Black)
+    }
+
+    //Find the table for the position, and whether its colors have to be swapped to look it up.
+    fn find(&self, board: &Board) -> Option<(&(Table, Option<Table>), bool)> {
+        let white = material(board, PieceColor::White);
+        let black = material(board, PieceColor::Black);
+        if let Some(entry) = self.tables.get(&format!("{}v{}", white, black)) {
+            return Some((entry, false));
+        }
+        self.tables.get(&format!("{}v{}", black, white)).map(|entry| (entry, true))
+    }
+
+    fn probe_table(&self, board: &Board, kind: TableKind, wdl: i32) -> Option<(i32, ProbeState)> {
+        let pieces = board.tiles.iter().flatten().flatten().count();
+        if pieces == 2 {return Some((0, ProbeState::Ok));}
+
+        let ((wdl_table, dtz_table), black_stronger) = self.find(board)?;
+        let entry = match kind {
+            TableKind::Wdl => wdl_table,
+            TableKind::Dtz => dtz_table.as_ref()?,
+        };
+        let table = entry.mapped()?;
+        let l = lookups();
+
+        let side_to_move = if let PieceColor::Black = board.to_move() {1} else {0};
+        let symmetric_black_to_move = entry.symmetric && side_to_move == 1;
+        let flip = symmetric_black_to_move || black_stronger;
+        let flip_color = if flip {8} else {0};
+        let flip_squares = if flip {56} else {0};
+        let stm = (flip as usize) ^ side_to_move;
+
+        let mut squares = [0usize; TBPIECES];
+        let mut pieces = [0u8; TBPIECES];
+        let mut size = 0;
+        let mut lead_pawns_cnt = 0;
+        let mut lead_pawns = [false; 64];
+        let mut tb_file = 0;
+
+        if entry.has_pawns {
+            //Pawns of the leading color come first in every sub-table.
+            let pc = entry.get(table, 0, 0).pieces[0] ^ flip_color;
+            let lead_color = if pc & 8 == 0 {PieceColor::White} else {PieceColor::Black};
+            for square in Square::all() {
+                if let Some(piece) = board[square] {
+                    if piece.color == lead_color && piece.piece == PieceType::Pawn {
+                        lead_pawns[square.index()] = true;
+                        squares[size] = square.index() ^ flip_squares;
+                        size += 1;
+                    }
+                }
+            }
+            lead_pawns_cnt = size;
+            let mut lead = 0;
+            for i in 1..lead_pawns_cnt {
+                if l.map_pawns[squares[i]] > l.map_pawns[squares[lead]] {lead = i;}
+            }
+            squares.swap(0, lead);
+            let file = squares[0] & 7;
+            tb_file = file.min(7 - file);
+        }
+
+        if let TableKind::Dtz = kind {
+            let flags = entry.get(table, stm, tb_file).flags;
+            if (entry.has_pawns || !entry.symmetric) && (flags & FLAG_STM) as usize != stm {
+                return Some((0, ProbeState::ChangeStm));
+            }
+        }
+
+        for square in Square::all() {
+            if lead_pawns[square.index()] {continue;}
+            if let Some(piece) = board[square] {
+                squares[size] = square.index() ^ flip_squares;
+                pieces[size] = tb_piece(piece) ^ flip_color;
+                size += 1;
+            }
+        }
+
+        let d = entry.get(table, stm, tb_file);
+
+        //Reorder the pieces to the sequence used by the table.
+        for i in lead_pawns_cnt..size - 1 {
+            for j in i + 1..size {
+                if d.pieces[i] == pieces[j] {
+                    pieces.swap(i, j);
+                    squares.swap(i, j);
+                    break;
+                }
+            }
+        }
+
+        //Mirror so that the leading piece is on files a-d.
+        if squares[0] & 7 > 3 {
+            for sq in squares.iter_mut().take(size) {
+                *sq ^= 7;
+            }
+        }
+
+        let mut idx: u64;
+        if entry.has_pawns {
+            idx = l.lead_pawn_idx[lead_pawns_cnt][squares[0]];
+            squares[1..lead_pawns_cnt].sort_by_key(|sq| l.map_pawns[*sq]);
+            for (i, sq) in squares.iter().enumerate().take(lead_pawns_cnt).skip(1) {
+                idx += l.binomial[i][l.map_pawns[*sq]];
+            }
+        } else {
+            //Without pawns, also mirror so the leading piece is on ranks 1-4...
+            if squares[0] >> 3 > 3 {
+                for sq in squares.iter_mut().take(size) {
+                    *sq ^= 56;
+                }
+            }
+            //...and below the a1-h8 diagonal.
+            for i in 0..d.group_len[0] {
+                if off_a1h8(squares[i]) == 0 {continue;}
+                if off_a1h8(squares[i]) > 0 {
+                    for sq in squares.iter_mut().take(size).skip(i) {
+                        *sq = ((*sq >> 3) | (*sq << 3)) & 63;
+                    }
+                }
+                break;
+            }
+
+            if entry.has_unique_pieces {
+                let adjust1 = (squares[1] > squares[0]) as usize;
+                let adjust2 = (squares[2] > squares[0]) as usize + (squares[2] > squares[1]) as usize;
+                idx = if off_a1h8(squares[0]) != 0 {
+                    ((l.map_a1d1d4[squares[0]] * 63 + (squares[1] - adjust1)) * 62 + squares[2] - adjust2) as u64
+                } else if off_a1h8(squares[1]) != 0 {
+                    ((6 * 63 + (squares[0] >> 3) * 28 + l.map_b1h1h7[squares[1]]) * 62 + squares[2] - adjust2) as u64
+                } else if off_a1h8(squares[2]) != 0 {
+                    (6 * 63 * 62 + 4 * 28 * 62 + (squares[0] >> 3) * 7 * 28 + ((squares[1] >> 3) - adjust1) * 28 + l.map_b1h1h7[squares[2]]) as u64
+                } else {
+                    (6 * 63 * 62 + 4 * 28 * 62 + 4 * 7 * 28 + (squares[0] >> 3) * 7 * 6 + ((squares[1] >> 3) - adjust1) * 6 + ((squares[2] >> 3) - adjust2)) as u64
+                };
+            } else {
+                idx = l.map_kk[l.map_a1d1d4[squares[0]]][squares[1]] as u64;
+            }
+        }
+
+        //Encode the remaining groups, each sorted by square.
+        idx *= d.group_idx[0];
+        let mut group_start = d.group_len[0];
+        let mut remaining_pawns = entry.has_pawns && entry.pawn_count[1] > 0;
+        let mut next = 1;
+        while d.group_len[next] != 0 {
+            let group_end = group_start + d.group_len[next];
+            squares[group_start..group_end].sort();
+            let mut n = 0u64;
+            for i in 0..d.group_len[next] {
+                let sq = squares[group_start + i];
+                let adjust = squares[..group_start].iter().filter(|prev| sq > **prev).count();
+                n += l.binomial[i + 1][sq - adjust - if remaining_pawns {8} else {0}];
+            }
+            remaining_pawns = false;
+            idx += n * d.group_idx[next];
+            group_start = group_end;
+            next += 1;
+        }
+
+        let value = d.decompress_pairs(&table.mmap, idx);
+        Some((entry.map_score(table, tb_file, value, wdl), ProbeState::Ok))
+    }
+
+    //Captures (and pawn moves for DTZ) are not stored reliably in the tables, so they are searched.
+    fn search(&self, board: &Board, check_zeroing_moves: bool) -> Option<(i32, ProbeState)> {
+        let moves = board.legal_moves();
+        let mut best_value = -2;
+        let mut move_count = 0;
+
+        for bmove in moves.iter() {
+            let pawn_move = board[bmove.from].is_some_and(|piece| piece.piece == PieceType::Pawn);
+            if !is_capture(board, bmove) && (!check_zeroing_moves || !pawn_move) {continue;}
+            move_count += 1;
+
+            let (value, _) = self.search(&after(board, bmove), false)?;
+            let value = -value;
+            if value > best_value {
+                best_value = value;
+                if value >= 2 {
+                    return Some((value, ProbeState::ZeroingBestMove));
+                }
+            }
+        }
+
+        let no_more_moves = move_count > 0 && move_count == moves.len();
+        let value = if no_more_moves {
+            best_value
+        } else {
+            self.probe_table(board, TableKind::Wdl, 0)?.0
+        };
+
+        if best_value >= value {
+            let state = if best_value > 0 || no_more_moves {ProbeState::ZeroingBestMove} else {ProbeState::Ok};
+            return Some((best_value, state));
+        }
+        Some((value, ProbeState::Ok))
+    }
+
+    pub fn probe_wdl(&self, board: &Board) -> Option<Wdl> {
+        if !self.can_probe(board) {return None;}
+        if !board.any_legal_moves() {
+            return Some(if board.is_check(board.to_move()) {Wdl::Loss} else {Wdl::Draw});
+        }
+        self.search(board, false).map(|(wdl, _)| Wdl::from_i32(wdl))
+    }
+
+    //Distance to zeroing of the 50-move counter in plies, from the point of view of the side to move.
+    //Positive is a win, negative a loss, values beyond 100 are drawn under the 50-move rule.
+    pub fn probe_dtz(&self, board: &Board) -> Option<i32> {
+        if !self.can_probe(board) {return None;}
+        if !board.any_legal_moves() {
+            return Some(if board.is_check(board.to_move()) {-1} else {0});
+        }
+        self.dtz(board)
+    }
+
+    fn dtz(&self, board: &Board) -> Option<i32> {
+        let (wdl, state) = self.search(board, true)?;
+        if wdl == 0 {return Some(0);}
+        if let ProbeState::ZeroingBestMove = state {
+            return Some(dtz_before_zeroing(wdl));
+        }
+
+        let (dtz, state) = self.probe_table(board, TableKind::Dtz, wdl)?;
+        if state != ProbeState::ChangeStm {
+            let cursed = if wdl == 1 || wdl == -1 {100} else {0};
+            return Some((dtz + cursed) * wdl.signum());
+        }
+
+        //The table stores the other side to move, so do a 1-ply search for the best dtz.
+        let mut min_dtz = 0xFFFF;
+        for bmove in board.legal_moves() {
+            let zeroing = is_zeroing(board, &bmove);
+            let next = after(board, &bmove);
+            let mut dtz = if zeroing {
+                -dtz_before_zeroing(self.search(&next, false)?.0)
+            } else if next.any_legal_moves() {
+                -self.dtz(&next)?
+            } else {
+                if next.is_check(next.to_move()) {1} else {0}
+            };
+            if dtz == 1 && next.is_check(next.to_move()) && !next.any_legal_moves() {
+                min_dtz = 1;
+            }
+            if !zeroing {
+                dtz += dtz.signum();
+            }
+            if dtz < min_dtz && dtz.signum() == wdl.signum() {
+                min_dtz = dtz;
+            }
+        }
+        Some(if min_dtz == 0xFFFF {-1} else {min_dtz})
+    }
+
+    //Rank a move for the side playing it, following the root move ranking of the original prober.
+    fn rank_move(&self, board: &Board, bmove: &SimpleMove) -> Option<(i32, i32)> {
+        let next = after(board, bmove);
+        let mut dtz = if next.halfmoves == 0 {
+            dtz_before_zeroing(-(self.probe_wdl(&next)? as i32))
+        } else {
+            let dtz = -self.probe_dtz(&next)?;
+            dtz + dtz.signum()
+        };
+        let mated = next.is_check(next.to_move()) && !next.any_legal_moves();
+        if mated && dtz == 2 {
+            dtz = 1;
+        }
+        let cnt50 = board.halfmoves as i32;
+        let rank = if dtz > 0 {
+            if dtz + cnt50 <= 99 {MAX_DTZ} else {MAX_DTZ - (dtz + cnt50)}
+        } else if dtz < 0 {
+            if -dtz * 2 + cnt50 < 100 {-MAX_DTZ} else {-MAX_DTZ + (-dtz + cnt50)}
+        } else {
+            0
+        };
+        Some((rank, dtz))
+    }
+
+    //Pick the move that keeps the best result, winning as quickly or losing as slowly as possible.
+    //Both come down to the lowest dtz among moves of equal rank.
+    pub fn best_move(&self, board: &Board) -> Option<SimpleMove> {
+        if !self.can_probe(board) {return None;}
+        let mut best: Option<(SimpleMove, i32, i32)> = None;
+        for bmove in board.legal_moves() {
+            let (rank, dtz) = self.rank_move(board, &bmove)?;
+            let better = match best {
+                None => true,
+                Some((_, best_rank, best_dtz)) => {
+                    rank > best_rank || (rank == best_rank && dtz < best_dtz)
+                }
+            };
+            if better {
+                best = Some((bmove, rank, dtz));
+            }
+        }
+        best.map(|(bmove, _, _)| bmove)
+    }
+
+    //Decide the game once the tablebase knows the result for certain. A win only counts if it can still be forced
+    //before the 50-move rule draws the game, which takes the DTZ table to know.
+    pub fn adjudicate(&self, board: &Board) -> Option<GameOverState> {
+        let mover = board.to_move();
+        let winner = match self.probe_wdl(board)? {
+            Wdl::Win => mover,
+            Wdl::Loss => !mover,
+            _ => return Some(GameOverState::Adjudication(None)),
+        };
+        let dtz = self.probe_dtz(board)?;
+        (dtz.unsigned_abs() as usize + board.halfmoves <= 100).then_some(GameOverState::Adjudication(Some(winner)))
+    }
+
+    //Readout for analysis mode, e.g. "Win in 13 (DTZ)".
+    pub fn describe(&self, board: &Board) -> Option<String> {
+        let wdl = self.probe_wdl(board)?;
+        let dtz = self.probe_dtz(board);
+        let side = if let PieceColor::White = board.to_move() {"White"} else {"Black"};
+        Some(match (wdl, dtz) {
+            (Wdl::Draw, _) => "Draw".to_string(),
+            (Wdl::Win, Some(dtz)) => format!("{} wins in {} (DTZ)", side, dtz.abs()),
+            (Wdl::Loss, Some(dtz)) => format!("{} loses in {} (DTZ)", side, dtz.abs()),
+            (Wdl::CursedWin, _) => format!("{}: cursed win", side),
+            (Wdl::BlessedLoss, _) => format!("{}: blessed loss", side),
+            (Wdl::Win, None) => format!("{} wins", side),
+            (Wdl::Loss, None) => format!("{} loses", side),
+        })
+    }
+}
+
+pub fn open_tablebase<P: AsRef<Path>>(path: P) -> io::Result<Arc<Tablebase>> {
+    Tablebase::open(&path.as_ref().to_string_lossy()).map(Arc::new)
+}
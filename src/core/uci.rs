@@ -0,0 +1,300 @@
+use std::{fmt, io::{BufRead, BufReader, Write}, path::PathBuf, process::{Child, ChildStdin, Command, Stdio}, str::FromStr, sync::mpsc::{self, Receiver, RecvTimeoutError}, thread, time::Duration};
+
+use rand::seq::SliceRandom;
+
+use super::board::{Board, GameOverState, MATE_SCORE, ParseError, Piece, SimpleMove};
+
+//Talking to Stockfish, or any other engine that speaks UCI.
+
+//Times a search that went wrong is tried again with a freshly started engine.
+const RESTARTS: usize = 1;
+
+//How long a UCI engine searches for each move.
+#[derive(Clone,Copy,PartialEq,Debug)]
+pub enum SearchLimit{
+    MoveTime(Duration),
+    //Plies.
+    Depth(u32),
+}
+
+impl SearchLimit {
+    fn go(&self) -> String {
+        match self {
+            SearchLimit::MoveTime(time) => format!("go movetime {}", time.as_millis()),
+            SearchLimit::Depth(depth) => format!("go depth {}", depth),
+        }
+    }
+
+    //How long the search is meant to take, when that is known up front.
+    fn duration(&self) -> Duration {
+        match self {
+            SearchLimit::MoveTime(time) => *time,
+            SearchLimit::Depth(_) => Duration::ZERO,
+        }
+    }
+}
+
+//What is played instead when the engine has no move for us, even after a restart.
+#[derive(Clone,Copy,PartialEq,Debug)]
+pub enum EngineFallback{
+    //The engine's side loses the game.
+    Forfeit,
+    //The built-in search plays the move, looking this many plies ahead.
+    Builtin{depth: u32},
+    //Any legal move.
+    Random,
+}
+
+#[derive(Clone,Copy,PartialEq,Debug)]
+pub struct ParseFallbackError;
+
+impl fmt::Display for ParseFallbackError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid engine fallback, expected forfeit, builtin, builtin:<depth> or random")
+    }
+}
+
+impl std::error::Error for ParseFallbackError {}
+
+//"forfeit", "random", or "builtin" with an optional depth such as "builtin:4".
+impl FromStr for EngineFallback {
+    type Err = ParseFallbackError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().split_once(':') {
+            None if s.trim() == "forfeit" => Ok(EngineFallback::Forfeit),
+            None if s.trim() == "random" => Ok(EngineFallback::Random),
+            None if s.trim() == "builtin" => Ok(EngineFallback::default()),
+            Some(("builtin", depth)) => depth.trim().parse().ok().filter(|depth| *depth > 0).map(|depth| EngineFallback::Builtin{depth}).ok_or(ParseFallbackError),
+            _ => Err(ParseFallbackError),
+        }
+    }
+}
+
+impl fmt::Display for EngineFallback {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EngineFallback::Forfeit => write!(f, "forfeit"),
+            EngineFallback::Builtin{depth} => write!(f, "builtin:{}", depth),
+            EngineFallback::Random => write!(f, "random"),
+        }
+    }
+}
+
+impl Default for EngineFallback {
+    fn default() -> Self {
+        EngineFallback::Builtin{depth: 3}
+    }
+}
+
+//Why the engine gave us no move.
+#[derive(Clone,PartialEq,Debug)]
+pub enum EngineError{
+    //Nothing could be run at the engine's path.
+    Start(String),
+    //The engine closed its output, which usually means it crashed.
+    Exited,
+    //The engine went quiet while we waited for this reply.
+    Timeout(String),
+    BadMove(ParseError),
+}
+
+impl fmt::Display for EngineError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EngineError::Start(e) => write!(f, "unable to run the engine: {}", e),
+            EngineError::Exited => write!(f, "the engine quit unexpectedly"),
+            EngineError::Timeout(reply) => write!(f, "the engine stopped responding while we waited for {}", reply),
+            EngineError::BadMove(e) => write!(f, "the engine's move was rejected: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for EngineError {}
+
+impl From<ParseError> for EngineError {
+    fn from(e: ParseError) -> Self {
+        EngineError::BadMove(e)
+    }
+}
+
+//How Stockfish, or any other UCI engine, is run.
+#[derive(Clone,PartialEq,Debug)]
+pub struct EngineSettings{
+    pub path: PathBuf,
+    pub limit: SearchLimit,
+    //UCI options set before every search, such as Threads or Hash.
+    pub options: Vec<(String, String)>,
+    //How long the engine may stay silent, on top of its move time, before it counts as hung.
+    pub timeout: Duration,
+    pub fallback: EngineFallback,
+}
+
+impl Default for EngineSettings {
+    fn default() -> Self {
+        EngineSettings {
+            path: PathBuf::from("stockfish/stockfish.exe"),
+            limit: SearchLimit::MoveTime(Duration::from_millis(1000)),
+            options: Vec::new(),
+            timeout: Duration::from_secs(10),
+            fallback: EngineFallback::default(),
+        }
+    }
+}
+
+//A running engine. Its output is read on a thread of its own, so waiting on it can give up after a while.
+struct EngineProcess{
+    child: Child,
+    stdin: ChildStdin,
+    lines: Receiver<String>,
+    timeout: Duration,
+}
+
+impl EngineProcess {
+    //Start the engine and wait until it has said it speaks UCI.
+    fn start(engine: &EngineSettings) -> Result<EngineProcess, EngineError> {
+        let mut child = Command::new(&engine.path)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()
+            .map_err(|e| EngineError::Start(format!("{}: {}", engine.path.display(), e)))?;
+        let (stdin, stdout) = (child.stdin.take().unwrap(), child.stdout.take().unwrap());
+        let (sender, lines) = mpsc::channel();
+        //Ends once the engine closes its output, which drops the sender and tells the receiving end it has gone.
+        thread::spawn(move || {
+            for line in BufReader::new(stdout).lines().map_while(Result::ok) {
+                if sender.send(line).is_err() {break;}
+            }
+        });
+        let mut process = EngineProcess {child, stdin, lines, timeout: engine.timeout};
+        process.send("uci")?;
+        process.wait_for("uciok", Duration::ZERO)?;
+        Ok(process)
+    }
+
+    fn send(&mut self, command: &str) -> Result<(), EngineError> {
+        writeln!(self.stdin, "{}", command).map_err(|_| EngineError::Exited)
+    }
+
+    //Everything printed before the line starting with `reply`, and that line. Gives up once the engine has been quiet
+    //for `extra` plus the timeout.
+    fn wait_for(&mut self, reply: &str, extra: Duration) -> Result<(String, String), EngineError> {
+        let mut before = String::new();
+        loop {
+            match self.lines.recv_timeout(extra + self.timeout) {
+                Ok(line) if line.split_whitespace().next() == Some(reply) => return Ok((before, line)),
+                Ok(line) => {
+                    before += &line;
+                    before.push('\n');
+                },
+                Err(RecvTimeoutError::Timeout) => return Err(EngineError::Timeout(reply.to_string())),
+                Err(RecvTimeoutError::Disconnected) => return Err(EngineError::Exited),
+            }
+        }
+    }
+}
+
+//The engine is only ever asked for one search, so it is stopped once we are done with it, however that went.
+impl Drop for EngineProcess {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+impl Board {
+    //Let Stockfish play a move, at a skill level from 0 to 20. An error leaves the board as it was.
+    pub fn engine_move(&mut self, engine: &EngineSettings, skill_level: u8) -> Result<(), EngineError> {
+        let (_, s) = self.run_engine(engine, &[("Skill Level", skill_level.min(20).to_string())], engine.limit)?;
+
+        let out = s.split_whitespace().nth(1).unwrap_or_default();
+        //Stockfish says (none) instead of 0000 when it has no move.
+        let out = if out == "(none)" {"0000"} else {out};
+        match self.parse_uci(out)? {
+            Some(bmove) => self.make_simple_move(&bmove),
+            //Passing is only right when there is nothing else to do.
+            None if self.any_legal_moves() => return Err(ParseError::Illegal(out.to_string()).into()),
+            None => {},
+        }
+        Ok(())
+    }
+
+    //Play a move the engine had no answer for, or give up the game on its behalf.
+    pub fn fallback_move(&mut self, fallback: EngineFallback) {
+        match fallback {
+            EngineFallback::Forfeit => self.outcome = Some(GameOverState::Forfeit(self.to_move())),
+            EngineFallback::Builtin{depth} => self.builtin_move(depth),
+            EngineFallback::Random => {
+                if let Some(bmove) = self.legal_moves().choose(&mut rand::thread_rng()) {
+                    self.make_simple_move(bmove);
+                }
+            },
+        }
+    }
+
+    //Search this position after setting the engine's own UCI options and then the given ones, returning the engine's
+    //output and its best move line. An engine that dies or hangs is started once more before we give up on it.
+    fn run_engine(&self, engine: &EngineSettings, options: &[(&str, String)], limit: SearchLimit) -> Result<(String, String), EngineError> {
+        let mut result = self.search_once(engine, options, limit);
+        for _ in 0..RESTARTS {
+            //Trying again won't make a missing engine appear.
+            if matches!(result, Ok(_) | Err(EngineError::Start(_))) {break;}
+            result = self.search_once(engine, options, limit);
+        }
+        result
+    }
+
+    fn search_once(&self, engine: &EngineSettings, options: &[(&str, String)], limit: SearchLimit) -> Result<(String, String), EngineError> {
+        let mut process = EngineProcess::start(engine)?;
+        //Chess960 castling is written as the king taking its own rook.
+        if !self.castles.is_standard() {
+            process.send("setoption name UCI_Chess960 value true")?;
+        }
+        let options = engine.options.iter().map(|(name, value)| (name.as_str(), value)).chain(options.iter().map(|(name, value)| (*name, value)));
+        for (name, value) in options {
+            process.send(&format!("setoption name {name} value {value}"))?;
+        }
+        process.send("isready")?;
+        process.wait_for("readyok", Duration::ZERO)?;
+        process.send(&self.uci_position())?;
+        process.send(&limit.go())?;
+        process.wait_for("bestmove", limit.duration())
+    }
+
+    //The position command for the game so far: where it started and every move since, so the engine sees repetitions.
+    pub fn uci_position(&self) -> String {
+        let mut out = match &self.start_fen {
+            Some(fen) => format!("position fen {}", fen),
+            None => "position startpos".to_string(),
+        };
+        if !self.movelist.is_empty() {out += " moves";}
+        let mut board = self.start_position();
+        for bmove in &self.movelist {
+            //Castling is written as the king's own move, or in Chess960 as the king taking its rook.
+            let ccheck = board.can_castle(bmove.from, bmove.to);
+            let to = match (ccheck.cancastle, ccheck.rooksource, ccheck.kingdestination) {
+                (true, Some(rook), Some(king)) => if self.castles.is_standard() {king} else {rook},
+                _ => bmove.to,
+            };
+            out += &format!(" {}", SimpleMove {from: bmove.from, to, promotion: bmove.promotion}.to_algebraic());
+            board.make_move(bmove.from, bmove.to, bmove.promotion.map(|piece| Piece{piece, color: bmove.color}));
+        }
+        out
+    }
+
+    //The engine's score for the side to move in centipawns, with a mate in n counted as MATE_SCORE - n.
+    pub fn engine_evaluation(&self, engine: &EngineSettings) -> Option<i32> {
+        let (info, _) = self.run_engine(engine, &[], SearchLimit::MoveTime(Duration::from_millis(500))).ok()?;
+        //The last score printed is from the deepest search.
+        info.lines().rev().find_map(|line| {
+            let mut words = line.split_whitespace().skip_while(|word| *word != "score").skip(1);
+            let (kind, value) = (words.next()?, words.next()?.parse::<i32>().ok()?);
+            match kind {
+                "cp" => Some(value),
+                "mate" if value > 0 => Some(MATE_SCORE - value),
+                "mate" => Some(-MATE_SCORE - value),
+                _ => None,
+            }
+        })
+    }
+}
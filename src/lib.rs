@@ -0,0 +1,4 @@
+pub mod core;
+
+#[cfg(feature = "bevy")]
+pub mod chess;
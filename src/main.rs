@@ -1,37 +1,68 @@
-//In percent
+//In percent, until changed in the settings file
 pub const MARGIN_LEFT: f32 = 10.0;
 pub const MARGIN_RIGHT: f32 = 20.0;
 pub const MARGIN_BOTTOM: f32 = 10.0;
 pub const MARGIN_TOP: f32 = 10.0;
 
+use std::time::Duration;
+
 use bevy::prelude::*;
 use rand::Rng;
-use bevy::window::WindowResized;
-use crate::chess::{ChessPluginClient, ChessPluginSettings, BoardEntity};
-use crate::chess::board::PieceColor;
+use bevy::window::{WindowMode, WindowResized};
+use clap::Parser;
+use chesslite::chess::{ChessPluginClient, ChessPluginSettings, BoardEntity, EngineKind, Margins};
+use chesslite::chess::setup::StartPosition;
+use chesslite::chess::theme::BoardTheme;
+use chesslite::chess::sound::SoundSettings;
+use chesslite::chess::resign::DrawPolicy;
+use chesslite::core::board::{PieceColor, Variant};
+use chesslite::core::uci::EngineSettings;
+use crate::cli::Cli;
+use crate::preferences::{Preferences, PreferencesPlugin};
 
-pub mod chess;
+mod cli;
+mod preferences;
 fn main() {
-    let mut rng = rand::thread_rng();
-    let color = if rng.gen_bool(0.5) {PieceColor::White} else {PieceColor::Black};
+    let cli = Cli::parse();
+    let (preferences, save) = match Preferences::load() {
+        Ok(preferences) => (preferences, true),
+        Err(e) => {
+            println!("{}, using the default settings", e);
+            (Preferences::default(), false)
+        },
+    };
+    let mut settings = default_settings();
+    preferences.apply(&mut settings);
+    let game = cli.apply(&mut settings);
+    let mode = if cli.fullscreen {WindowMode::BorderlessFullscreen} else {WindowMode::Windowed};
+    let resolution = (preferences.window.width, preferences.window.height).into();
     App::new()
-    .add_plugins((DefaultPlugins,ChessPluginClient{ settings: ChessPluginSettings {color, engine: true}}))
+    .add_plugins((DefaultPlugins.set(WindowPlugin {primary_window: Some(Window {mode, resolution, ..default()}), ..default()}),ChessPluginClient{settings, game}))
+    .add_plugins(PreferencesPlugin{preferences, save})
     .add_systems(Update, window_resized_event)
     .run();
 }
 
-fn window_resized_event(mut events: EventReader<WindowResized>, mut boardents: Query<&mut Transform, With<BoardEntity>>) {
+//Settings before the settings file and the command line are applied, with a random side to play.
+fn default_settings() -> ChessPluginSettings {
+    let mut rng = rand::thread_rng();
+    let color = if rng.gen_bool(0.5) {PieceColor::White} else {PieceColor::Black};
+    ChessPluginSettings {color, engine: true, engine_kind: EngineKind::Stockfish{skill: 20}, engine_settings: EngineSettings::default(), book: None, tablebase: None, analysis: false, time_control: None, takebacks: true, move_hints: true, theme: BoardTheme::default(), animation_duration: Duration::from_millis(200), piece_set: "classic".to_string(), sounds: Some(SoundSettings{pack: "default".to_string(), volume: 0.5}), draw_policy: DrawPolicy::Evaluation(0), start_position: StartPosition::Standard, variant: Variant::Standard, setup_menu: true, margins: Margins{left: MARGIN_LEFT, right: MARGIN_RIGHT, bottom: MARGIN_BOTTOM, top: MARGIN_TOP}}
+}
+
+fn window_resized_event(mut events: EventReader<WindowResized>, settings: Res<ChessPluginSettings>, mut boardents: Query<&mut Transform, With<BoardEntity>>) {
     let event: Option<&WindowResized> = events.read().last(); // might be able to unwrap here idk
     let resized: &WindowResized = match event {
         Some(x) => x,
         None => return
     };
     //println!("{resized:?}");
-    let hori_mult = (MARGIN_LEFT - MARGIN_RIGHT) / 200.0;
-    let vert_mult = (MARGIN_BOTTOM - MARGIN_TOP) / 200.0;
+    let margins = settings.margins;
+    let hori_mult = (margins.left - margins.right) / 200.0;
+    let vert_mult = (margins.bottom - margins.top) / 200.0;
     let center = Vec3::new(hori_mult * resized.width, vert_mult * resized.height,0.0);
-    let hori_percent = (100.0 - (MARGIN_RIGHT + MARGIN_LEFT)) / 100.0;
-    let vert_percent = (100.0 - (MARGIN_TOP + MARGIN_BOTTOM)) / 100.0;
+    let hori_percent = (100.0 - (margins.right + margins.left)) / 100.0;
+    let vert_percent = (100.0 - (margins.top + margins.bottom)) / 100.0;
     let space = (hori_percent * resized.width).min(vert_percent * resized.height);
     for mut boardent in boardents.iter_mut(){
         boardent.translation = center;
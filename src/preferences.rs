@@ -0,0 +1,359 @@
+use std::{collections::BTreeMap, fs, path::{Path, PathBuf}, time::Duration};
+
+use bevy::{
+    app::AppExit,
+    prelude::*,
+    window::{PrimaryWindow, WindowMode, WindowResized},
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{MARGIN_BOTTOM, MARGIN_LEFT, MARGIN_RIGHT, MARGIN_TOP};
+use chesslite::chess::{ChessPluginSettings, Margins};
+use chesslite::chess::pieceset::PieceSetLibrary;
+use chesslite::chess::sound::SoundPack;
+use chesslite::chess::theme::BoardTheme;
+use chesslite::core::book::{BookSelection, BookSettings};
+use chesslite::core::syzygy::TablebaseSettings;
+use chesslite::core::uci::{EngineSettings, SearchLimit};
+
+//Saved when the program closes, so it keeps any theme, piece set, volume, clock or window size picked while it ran.
+pub struct PreferencesPlugin{
+    pub preferences: Preferences,
+    //Off when the file couldn't be read, so a hand-edited file with a mistake in it isn't overwritten.
+    pub save: bool,
+}
+
+impl Plugin for PreferencesPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(self.preferences.clone());
+        if self.save {
+            app
+            .add_systems(Update, remember_preferences)
+            .add_systems(Last, save_preferences);
+        }
+    }
+}
+
+//What the player likes, kept from one run to the next in settings.toml in the platform's config directory.
+#[derive(Resource,Clone,PartialEq,Debug,Serialize,Deserialize)]
+#[serde(default)]
+pub struct Preferences{
+    //Name of a preset or of a theme in assets/themes.
+    pub theme: String,
+    pub piece_set: String,
+    //Clock for new games in PGN TimeControl syntax, none for no clock.
+    pub time_control: Option<String>,
+    pub move_hints: bool,
+    pub analysis: bool,
+    //From 0 (silent) to 1.
+    pub volume: f32,
+    pub engine: EnginePreferences,
+    pub book: BookPreferences,
+    pub tablebase: TablebasePreferences,
+    pub window: WindowPreferences,
+    pub margins: Margins,
+}
+
+#[derive(Clone,PartialEq,Debug,Serialize,Deserialize)]
+#[serde(default)]
+pub struct EnginePreferences{
+    pub path: PathBuf,
+    //Milliseconds to think per move. A depth takes its place when one is given.
+    pub movetime: u64,
+    pub depth: Option<u32>,
+    //UCI options set before every search, e.g. `Threads = 4`.
+    pub options: BTreeMap<String, toml::Value>,
+    //Seconds the engine may go quiet, on top of its move time, before it is restarted.
+    pub timeout: f32,
+    //What plays when the engine can't: forfeit, builtin, builtin:<depth> or random.
+    pub fallback: String,
+}
+
+//Polyglot opening book the computer plays from before it starts thinking.
+#[derive(Clone,PartialEq,Debug,Serialize,Deserialize)]
+#[serde(default)]
+pub struct BookPreferences{
+    //No book when left out.
+    pub path: Option<PathBuf>,
+    //Plies from the start of the game the book is used for.
+    pub depth: usize,
+    //weighted or best.
+    pub selection: String,
+}
+
+//Syzygy endgame tablebases, for the computer's moves, adjudication and the analysis readout.
+#[derive(Clone,PartialEq,Debug,Default,Serialize,Deserialize)]
+#[serde(default)]
+pub struct TablebasePreferences{
+    //Directories separated by ':' (';' on Windows), no tablebases when left out.
+    pub path: Option<String>,
+    //End games as soon as the tablebases know the result.
+    pub adjudicate: bool,
+}
+
+#[derive(Clone,Copy,PartialEq,Debug,Serialize,Deserialize)]
+#[serde(default)]
+pub struct WindowPreferences{
+    pub width: f32,
+    pub height: f32,
+}
+
+impl Default for Preferences {
+    fn default() -> Self {
+        Preferences {
+            theme: BoardTheme::default().name,
+            piece_set: "classic".to_string(),
+            time_control: None,
+            move_hints: true,
+            analysis: false,
+            volume: 0.5,
+            engine: EnginePreferences::default(),
+            book: BookPreferences::default(),
+            tablebase: TablebasePreferences::default(),
+            window: WindowPreferences::default(),
+            margins: Margins {left: MARGIN_LEFT, right: MARGIN_RIGHT, bottom: MARGIN_BOTTOM, top: MARGIN_TOP},
+        }
+    }
+}
+
+impl Default for EnginePreferences {
+    fn default() -> Self {
+        let engine = EngineSettings::default();
+        EnginePreferences {path: engine.path, movetime: 1000, depth: None, options: BTreeMap::new(), timeout: engine.timeout.as_secs_f32(), fallback: engine.fallback.to_string()}
+    }
+}
+
+impl Default for BookPreferences {
+    fn default() -> Self {
+        let book = BookSettings::new(String::new());
+        BookPreferences {path: None, depth: book.max_depth, selection: book.selection.to_string()}
+    }
+}
+
+impl Default for WindowPreferences {
+    fn default() -> Self {
+        WindowPreferences {width: 1280.0, height: 720.0}
+    }
+}
+
+impl Preferences {
+    pub fn path() -> Option<PathBuf> {
+        dirs::config_dir().map(|dir| dir.join("chesslite").join("settings.toml"))
+    }
+
+    //The saved preferences, or the defaults if there are none yet.
+    pub fn load() -> Result<Preferences, String> {
+        let Some(path) = Preferences::path() else {return Ok(Preferences::default())};
+        Preferences::read(&path)
+    }
+
+    fn read(path: &Path) -> Result<Preferences, String> {
+        match fs::read_to_string(path) {
+            Ok(text) => toml::from_str(&text).map_err(|e| format!("invalid settings in {}: {}", path.display(), e)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Preferences::default()),
+            Err(e) => Err(format!("unable to read {}: {}", path.display(), e)),
+        }
+    }
+
+    pub fn save(&self) -> Result<(), String> {
+        let path = Preferences::path().ok_or("no config directory")?;
+        let text = toml::to_string_pretty(self).map_err(|e| e.to_string())?;
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).map_err(|e| format!("unable to create {}: {}", dir.display(), e))?;
+        }
+        fs::write(&path, text).map_err(|e| format!("unable to write {}: {}", path.display(), e))
+    }
+
+    pub fn apply(&self, settings: &mut ChessPluginSettings) {
+        match BoardTheme::named(&self.theme) {
+            Some(theme) => settings.theme = theme,
+            None => println!("Theme {} not found, using {}", self.theme, settings.theme.name),
+        }
+        settings.piece_set = self.piece_set.clone();
+        settings.time_control = self.time_control.as_ref().and_then(|control| control.parse().map_err(|_| {
+            println!("Invalid time control {} in the settings", control);
+        }).ok());
+        settings.move_hints = self.move_hints;
+        settings.analysis = self.analysis;
+        if let Some(sounds) = &mut settings.sounds {
+            sounds.volume = self.volume;
+        }
+        settings.engine_settings.path = self.engine.path.clone();
+        settings.engine_settings.limit = match self.engine.depth {
+            Some(depth) => SearchLimit::Depth(depth),
+            None => SearchLimit::MoveTime(Duration::from_millis(self.engine.movetime)),
+        };
+        settings.engine_settings.options = self.engine.options.iter().map(|(name, value)| {
+            //Strings are sent without their quotes, everything else as TOML writes it.
+            let value = match value {
+                toml::Value::String(value) => value.clone(),
+                value => value.to_string(),
+            };
+            (name.clone(), value)
+        }).collect();
+        match Duration::try_from_secs_f32(self.engine.timeout) {
+            Ok(timeout) => settings.engine_settings.timeout = timeout,
+            Err(_) => println!("Invalid engine timeout {} in the settings", self.engine.timeout),
+        }
+        match self.engine.fallback.parse() {
+            Ok(fallback) => settings.engine_settings.fallback = fallback,
+            Err(_) => println!("Invalid engine fallback {} in the settings", self.engine.fallback),
+        }
+        if let Some(path) = &self.book.path {
+            let selection = self.book.selection.parse().unwrap_or_else(|_| {
+                println!("Invalid book selection {} in the settings", self.book.selection);
+                BookSelection::WeightedRandom
+            });
+            settings.book = Some(BookSettings {path: path.to_string_lossy().into_owned(), selection, max_depth: self.book.depth});
+        }
+        if let Some(path) = &self.tablebase.path {
+            settings.tablebase = Some(TablebaseSettings {path: path.clone(), adjudicate: self.tablebase.adjudicate});
+        }
+        settings.margins = self.margins;
+    }
+}
+
+//Keep what the player changes while the program runs. Values given on the command line are left out, as they are
+//only there when the program starts.
+fn remember_preferences(
+    mut preferences: ResMut<Preferences>,
+    settings: Res<ChessPluginSettings>,
+    theme: Res<BoardTheme>,
+    piece_sets: Option<Res<PieceSetLibrary>>,
+    sound_pack: Option<Res<SoundPack>>,
+    mut er_resized: EventReader<WindowResized>,
+    q_window: Query<&Window, With<PrimaryWindow>>,
+    //The clock settings had when last looked at, as new games are set up from the menu.
+    mut time_control: Local<Option<Option<String>>>,
+) {
+    if theme.is_changed() && !theme.is_added() {
+        preferences.theme = theme.name.clone();
+    }
+    if let Some(piece_sets) = piece_sets.filter(|piece_sets| piece_sets.is_changed() && !piece_sets.is_added()) {
+        preferences.piece_set = piece_sets.current().name.clone();
+    }
+    if let Some(sound_pack) = sound_pack.filter(|sound_pack| sound_pack.is_changed() && !sound_pack.is_added()) {
+        preferences.volume = sound_pack.volume;
+    }
+    let control = settings.time_control.as_ref().map(ToString::to_string);
+    if time_control.as_ref().is_some_and(|seen| *seen != control) {
+        preferences.time_control = control.clone();
+    }
+    *time_control = Some(control);
+    //A fullscreen window says nothing about the size the player wants it back at.
+    if q_window.get_single().is_ok_and(|window| window.mode == WindowMode::Windowed) {
+        if let Some(resized) = er_resized.read().last() {
+            preferences.window = WindowPreferences {width: resized.width, height: resized.height};
+        }
+    }
+}
+
+fn save_preferences(mut er_exit: EventReader<AppExit>, preferences: Res<Preferences>) {
+    if er_exit.read().last().is_none() {return;}
+    if let Err(e) = preferences.save() {
+        println!("Unable to save settings: {}", e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{fs, time::Duration};
+
+    use chesslite::core::book::BookSelection;
+    use chesslite::core::uci::{EngineSettings, SearchLimit};
+
+    use super::Preferences;
+
+    fn option<'a>(options: &'a [(String, String)], name: &str) -> Option<&'a str> {
+        options.iter().find(|(option, _)| option == name).map(|(_, value)| value.as_str())
+    }
+
+    #[test]
+    fn round_trips_through_toml() {
+        let defaults = Preferences::default();
+        let text = toml::to_string_pretty(&defaults).unwrap();
+        assert_eq!(toml::from_str::<Preferences>(&text).unwrap(), defaults);
+
+        let mut settings = crate::default_settings();
+        defaults.apply(&mut settings);
+        assert_eq!(settings.engine_settings.limit, SearchLimit::MoveTime(Duration::from_millis(1000)));
+        assert_eq!(settings.engine_settings.fallback, EngineSettings::default().fallback);
+        assert!(settings.engine_settings.options.is_empty());
+        assert!(settings.book.is_none() && settings.tablebase.is_none() && settings.time_control.is_none());
+    }
+
+    #[test]
+    fn applies_engine_options_and_limits() {
+        let preferences: Preferences = toml::from_str(r#"
+            time_control = "300+5"
+
+            [engine]
+            depth = 12
+            fallback = "random"
+
+            [engine.options]
+            Threads = 4
+            UCI_ShowWDL = true
+            "Debug Log File" = "engine log.txt"
+
+            [book]
+            path = "book.bin"
+            selection = "best"
+        "#).unwrap();
+        let text = toml::to_string_pretty(&preferences).unwrap();
+        assert_eq!(toml::from_str::<Preferences>(&text).unwrap(), preferences);
+
+        let mut settings = crate::default_settings();
+        preferences.apply(&mut settings);
+        let engine = &settings.engine_settings;
+        assert_eq!(engine.limit, SearchLimit::Depth(12));
+        assert_eq!(engine.fallback.to_string(), "random");
+        //Strings lose their quotes, other values are written as TOML does.
+        assert_eq!(option(&engine.options, "Threads"), Some("4"));
+        assert_eq!(option(&engine.options, "UCI_ShowWDL"), Some("true"));
+        assert_eq!(option(&engine.options, "Debug Log File"), Some("engine log.txt"));
+        assert_eq!(settings.time_control.map(|control| control.to_string()).as_deref(), Some("300+5"));
+        let book = settings.book.unwrap();
+        assert_eq!((book.path.as_str(), book.selection, book.max_depth), ("book.bin", BookSelection::BestWeight, 16));
+    }
+
+    #[test]
+    fn keeps_the_defaults_for_invalid_values() {
+        let preferences: Preferences = toml::from_str(r#"
+            time_control = "soon"
+
+            [engine]
+            timeout = -1.0
+            fallback = "sometimes"
+
+            [book]
+            path = "book.bin"
+            selection = "heaviest"
+        "#).unwrap();
+        let mut settings = crate::default_settings();
+        preferences.apply(&mut settings);
+        let defaults = EngineSettings::default();
+        assert!(settings.time_control.is_none());
+        assert_eq!(settings.engine_settings.timeout, defaults.timeout);
+        assert_eq!(settings.engine_settings.fallback, defaults.fallback);
+        assert_eq!(settings.book.unwrap().selection, BookSelection::WeightedRandom);
+    }
+
+    #[test]
+    fn reads_missing_partial_and_broken_files() {
+        let dir = std::env::temp_dir().join(format!("chesslite-preferences-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        assert_eq!(Preferences::read(&dir.join("missing.toml")), Ok(Preferences::default()));
+
+        let partial = dir.join("partial.toml");
+        fs::write(&partial, "volume = 0.25\n[window]\nwidth = 800.0\n").unwrap();
+        let preferences = Preferences::read(&partial).unwrap();
+        assert_eq!((preferences.volume, preferences.window.width, preferences.window.height), (0.25, 800.0, 720.0));
+        assert_eq!(preferences.engine, Preferences::default().engine);
+
+        let broken = dir.join("broken.toml");
+        fs::write(&broken, "volume = loud\n").unwrap();
+        assert!(Preferences::read(&broken).unwrap_err().contains("broken.toml"));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
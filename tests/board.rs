@@ -0,0 +1,21 @@
+use chesslite::core::board::Board;
+use chesslite::core::square::Square;
+
+fn square(name: &str) -> Square {
+    name.parse().unwrap()
+}
+
+#[test]
+fn counts_en_passant_as_a_capture() {
+    let mut board = Board::from_fen("4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 1").unwrap();
+    board.make_move(square("e5"), square("d6"), None);
+    assert!(board.lastmove.unwrap().capture);
+    assert_eq!(board[square("d5")], None);
+
+    //Castling onto a square isn't a capture, nor is a quiet move.
+    let mut board = Board::from_fen("4k3/8/8/8/8/8/8/R3K2R w KQ - 0 1").unwrap();
+    board.make_move(square("e1"), square("g1"), None);
+    assert!(!board.lastmove.unwrap().capture);
+    board.make_move(square("e8"), square("d8"), None);
+    assert!(!board.lastmove.unwrap().capture);
+}
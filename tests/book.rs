@@ -0,0 +1,39 @@
+use chesslite::core::board::Board;
+use chesslite::core::book::{BookSelection, BookSettings};
+
+fn after(moves: &[&str]) -> Board {
+    let mut board = Board::new();
+    for uci in moves {
+        let bmove = board.parse_uci(uci).unwrap().unwrap();
+        board.make_simple_move(&bmove);
+    }
+    board
+}
+
+//The reference keys from the Polyglot book format description.
+#[test]
+fn polyglot_keys_match_the_reference() {
+    let cases: [(&[&str], u64); 9] = [
+        (&[], 0x463b96181691fc9c),
+        (&["e2e4"], 0x823c9b50fd114196),
+        (&["e2e4", "d7d5"], 0x0756b94461c50fb0),
+        (&["e2e4", "d7d5", "e4e5"], 0x662fafb965db29d4),
+        (&["e2e4", "d7d5", "e4e5", "f7f5"], 0x22a48b5a8e47ff78),
+        (&["e2e4", "d7d5", "e4e5", "f7f5", "e1e2"], 0x652a607ca3f242c1),
+        (&["e2e4", "d7d5", "e4e5", "f7f5", "e1e2", "e8f7"], 0x00fdd303c946bdd9),
+        (&["a2a4", "b7b5", "h2h4", "b5b4", "c2c4"], 0x3c8123ea7b067637),
+        (&["a2a4", "b7b5", "h2h4", "b5b4", "c2c4", "b4c3", "a1a3"], 0x5c3f9b829b279560),
+    ];
+    for (moves, key) in cases {
+        assert_eq!(after(moves).polyglot_key(), key, "after {:?}", moves);
+    }
+}
+
+#[test]
+fn parses_selections() {
+    assert_eq!("weighted".parse(), Ok(BookSelection::WeightedRandom));
+    assert_eq!("best".parse(), Ok(BookSelection::BestWeight));
+    assert!("first".parse::<BookSelection>().is_err());
+    let book = BookSettings::new("book.bin".to_string());
+    assert_eq!(book.selection.to_string().parse(), Ok(book.selection));
+}
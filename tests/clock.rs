@@ -0,0 +1,117 @@
+use std::time::Duration;
+
+use chesslite::chess::clock::{timeout, GameClock, Increment, TimeControl, TimePeriod};
+use chesslite::core::board::{Board, GameOverState, PieceColor};
+
+fn secs(seconds: u64) -> Duration {
+    Duration::from_secs(seconds)
+}
+
+//A clock for the given control with White's time running.
+fn clock(control: &str) -> GameClock {
+    let mut clock = GameClock::new(Some(control.parse().unwrap()));
+    clock.start(PieceColor::White);
+    clock
+}
+
+fn period(moves: Option<u32>, seconds: u64) -> TimePeriod {
+    TimePeriod {moves, time: Duration::from_secs(seconds)}
+}
+
+#[test]
+fn parses_pgn_time_controls() {
+    assert_eq!("300".parse(), Ok(TimeControl::sudden_death(Duration::from_secs(300))));
+    assert_eq!("300+5".parse(), Ok(TimeControl::fischer(Duration::from_secs(300), Duration::from_secs(5))));
+    assert_eq!("40/5400:1800+30".parse(), Ok(TimeControl {
+        periods: vec![period(Some(40), 5400), period(None, 1800)],
+        increment: Increment::Fischer(Duration::from_secs(30)),
+    }));
+    assert_eq!("600+d5".parse::<TimeControl>().map(|control| control.increment), Ok(Increment::Delay(Duration::from_secs(5))));
+    assert_eq!("600+b5".parse::<TimeControl>().map(|control| control.increment), Ok(Increment::Bronstein(Duration::from_secs(5))));
+    assert_eq!("0.5+0.1".parse::<TimeControl>().map(|control| control.periods[0].time), Ok(Duration::from_millis(500)));
+}
+
+#[test]
+fn rejects_bad_time_controls() {
+    for text in ["", "abc", "-5", "300+", "300+x5", "/300", "40/", "inf", "NaN", "1e30", "300+inf", "40/300:1e30"] {
+        assert!(text.parse::<TimeControl>().is_err(), "{}", text);
+    }
+}
+
+#[test]
+fn writes_what_it_parses() {
+    for text in ["300", "300+5", "40/5400:1800+30", "40/7200:20/3600:900+d5", "600+b5", "0.5+0.1", "90.25"] {
+        let control = text.parse::<TimeControl>().unwrap();
+        assert_eq!(control.to_string(), text);
+        assert_eq!(control.to_string().parse(), Ok(control));
+    }
+}
+
+#[test]
+fn fischer_adds_the_increment_after_each_move() {
+    let mut clock = clock("300+5");
+    assert_eq!(clock.tick(secs(10)), None);
+    clock.press(PieceColor::White);
+    assert_eq!(clock.remaining(PieceColor::White), secs(295));
+    assert_eq!(clock.running(), Some(PieceColor::Black));
+    clock.tick(secs(1));
+    assert_eq!(clock.remaining(PieceColor::Black), secs(299));
+}
+
+#[test]
+fn bronstein_gives_back_the_time_used_up_to_the_delay() {
+    let mut clock = clock("600+b5");
+    clock.tick(secs(3));
+    clock.press(PieceColor::White);
+    assert_eq!(clock.remaining(PieceColor::White), secs(600));
+    clock.press(PieceColor::Black);
+    clock.tick(secs(8));
+    clock.press(PieceColor::White);
+    assert_eq!(clock.remaining(PieceColor::White), secs(597));
+}
+
+#[test]
+fn delay_waits_before_counting_down() {
+    let mut clock = clock("600+d5");
+    clock.tick(secs(3));
+    assert_eq!(clock.remaining(PieceColor::White), secs(600));
+    clock.tick(secs(4));
+    assert_eq!(clock.remaining(PieceColor::White), secs(598));
+    clock.press(PieceColor::White);
+    assert_eq!(clock.remaining(PieceColor::White), secs(598));
+    //The delay starts over with the next move.
+    clock.press(PieceColor::Black);
+    clock.tick(secs(5));
+    assert_eq!(clock.remaining(PieceColor::White), secs(598));
+}
+
+#[test]
+fn adds_the_next_period_once_its_moves_are_made() {
+    let mut clock = clock("2/60:30");
+    clock.tick(secs(10));
+    clock.press(PieceColor::White);
+    clock.press(PieceColor::Black);
+    assert_eq!(clock.remaining(PieceColor::White), secs(50));
+    clock.press(PieceColor::White);
+    assert_eq!(clock.remaining(PieceColor::White), secs(80));
+    assert_eq!(clock.remaining(PieceColor::Black), secs(60));
+}
+
+#[test]
+fn flags_the_side_that_runs_out() {
+    let mut clock = clock("60+5");
+    assert_eq!(clock.tick(secs(59)), None);
+    assert_eq!(clock.tick(secs(2)), Some(PieceColor::White));
+    assert_eq!(clock.remaining(PieceColor::White), Duration::ZERO);
+    assert_eq!(clock.running(), None);
+    assert_eq!(clock.tick(secs(1)), None);
+}
+
+#[test]
+fn runs_out_against_insufficient_material() {
+    let queen = Board::from_fen("4k3/8/8/8/8/8/3q4/4K3 w - - 0 1").unwrap();
+    assert_eq!(timeout(&queen, PieceColor::White), GameOverState::Timeout(PieceColor::White));
+    let bare = Board::from_fen("4k3/8/8/8/8/8/3Q4/4K3 w - - 0 1").unwrap();
+    assert_eq!(timeout(&bare, PieceColor::White), GameOverState::TimeoutVsInsufficientMaterial(PieceColor::White));
+    assert_eq!(timeout(&bare, PieceColor::Black), GameOverState::Timeout(PieceColor::Black));
+}
@@ -0,0 +1,354 @@
+#!/usr/bin/env python3
+# Writes the small Syzygy tables the tablebase tests probe: KQvK and KRvK, WDL and DTZ.
+#
+# These two are simple enough to solve here by retrograde analysis. They are written the way the real generator
+# writes them, with symbol pairs, a canonical Huffman code and DTZ values remapped by frequency, so the prober reads
+# them through the same paths as the published tables.
+#
+#     python3 tests/fixtures/syzygy/generate.py
+
+import heapq
+import os
+import struct
+from collections import Counter
+
+WDL_MAGIC = bytes([0x71, 0xE8, 0x23, 0x5D])
+DTZ_MAGIC = bytes([0xD7, 0x66, 0x0C, 0xA5])
+FLAG_WIN_PLIES = 4
+FLAG_MAPPED = 2
+FLAG_LOSS_PLIES = 8
+FLAG_SINGLE_VALUE = 128
+
+KING, QUEEN, ROOK = 6, 5, 4
+BLACK = 8
+
+KING_STEPS = [(-1, -1), (-1, 0), (-1, 1), (0, -1), (0, 1), (1, -1), (1, 0), (1, 1)]
+SLIDES = {QUEEN: KING_STEPS, ROOK: [(-1, 0), (1, 0), (0, -1), (0, 1)]}
+
+BLOCK_LOG2 = 5
+SPAN_LOG2 = 8
+MAX_SYMBOLS = 1024
+MIN_PAIR_COUNT = 8
+
+
+def step(sq, df, dr):
+    f, r = (sq & 7) + df, (sq >> 3) + dr
+    return r * 8 + f if 0 <= f < 8 and 0 <= r < 8 else None
+
+
+def adjacent(a, b):
+    return max(abs((a & 7) - (b & 7)), abs((a >> 3) - (b >> 3))) <= 1
+
+
+def king_moves(sq):
+    return [to for to in (step(sq, df, dr) for df, dr in KING_STEPS) if to is not None]
+
+
+def slides(piece, sq, blockers):
+    out = []
+    for df, dr in SLIDES[piece]:
+        to = step(sq, df, dr)
+        while to is not None and to not in blockers:
+            out.append(to)
+            to = step(to, df, dr)
+    return out
+
+
+# Positions are (white king, white piece, black king). Wins for white to move and losses for black to move are in
+# plies to mate; no zeroing move helps either side in these endings, so that is also the distance to zeroing.
+def solve(piece):
+    def legal(wk, wx, bk, white_to_move):
+        if len({wk, wx, bk}) < 3 or adjacent(wk, bk):
+            return False
+        return not white_to_move or bk not in slides(piece, wx, {wk})
+
+    def white_moves(wk, wx, bk):
+        for to in king_moves(wk):
+            if to != wx and not adjacent(to, bk):
+                yield (to, wx, bk)
+        for to in slides(piece, wx, {wk, bk}):
+            yield (wk, to, bk)
+
+    def black_moves(wk, wx, bk):
+        # None stands for taking the white piece, which leaves a draw.
+        attacked = set(slides(piece, wx, {wk}))
+        for to in king_moves(bk):
+            if adjacent(to, wk) or to in attacked:
+                continue
+            yield None if to == wx else (wk, wx, to)
+
+    positions = [(wk, wx, bk) for wk in range(64) for wx in range(64) for bk in range(64)]
+    wtm = {}
+    btm = {}
+    remaining = {}
+    frontier = []
+    for pos in positions:
+        if not legal(*pos, False):
+            continue
+        moves = list(black_moves(*pos))
+        if None in moves:
+            btm[pos] = None
+        elif not moves:
+            in_check = pos[2] in slides(piece, pos[1], {pos[0]})
+            btm[pos] = 0 if in_check else None
+            if in_check:
+                frontier.append(pos)
+        else:
+            remaining[pos] = len(moves)
+
+    plies = 0
+    while frontier:
+        plies += 1
+        wins = []
+        for lost in frontier:
+            # Positions where white could play into the lost one, found by playing white's moves from it backwards.
+            wk, wx, bk = lost
+            for before in white_moves(wk, wx, bk):
+                if legal(*before, True) and before not in wtm:
+                    wtm[before] = plies
+                    wins.append(before)
+        plies += 1
+        frontier = []
+        for won in wins:
+            wk, wx, bk = won
+            for to in king_moves(bk):
+                before = (wk, wx, to)
+                if before in remaining:
+                    remaining[before] -= 1
+                    if remaining[before] == 0:
+                        del remaining[before]
+                        btm[before] = plies
+                        frontier.append(before)
+    for pos in remaining:
+        btm[pos] = None
+    for pos in positions:
+        if legal(*pos, True) and pos not in wtm:
+            wtm[pos] = None
+    return wtm, btm
+
+
+def lookups():
+    off_a1h8 = lambda sq: (sq >> 3) - (sq & 7)
+    map_b1h1h7 = [0] * 64
+    code = 0
+    for sq in range(64):
+        if off_a1h8(sq) < 0:
+            map_b1h1h7[sq] = code
+            code += 1
+    map_a1d1d4 = [0] * 64
+    code = 0
+    diagonal = []
+    for sq in range(28):
+        if off_a1h8(sq) < 0 and (sq & 7) <= 3:
+            map_a1d1d4[sq] = code
+            code += 1
+        elif off_a1h8(sq) == 0 and (sq & 7) <= 3:
+            diagonal.append(sq)
+    for sq in diagonal:
+        map_a1d1d4[sq] = code
+        code += 1
+    return off_a1h8, map_b1h1h7, map_a1d1d4
+
+
+OFF_A1H8, MAP_B1H1H7, MAP_A1D1D4 = lookups()
+
+
+# Index of three unique pieces, given in the order the table lists them, as the prober computes it.
+def index(squares):
+    s = list(squares)
+    if s[0] & 7 > 3:
+        s = [sq ^ 7 for sq in s]
+    if s[0] >> 3 > 3:
+        s = [sq ^ 56 for sq in s]
+    for i in range(3):
+        if OFF_A1H8(s[i]) == 0:
+            continue
+        if OFF_A1H8(s[i]) > 0:
+            s = s[:i] + [((sq >> 3) | (sq << 3)) & 63 for sq in s[i:]]
+        break
+    adjust1 = int(s[1] > s[0])
+    adjust2 = int(s[2] > s[0]) + int(s[2] > s[1])
+    if OFF_A1H8(s[0]) != 0:
+        return (MAP_A1D1D4[s[0]] * 63 + (s[1] - adjust1)) * 62 + s[2] - adjust2
+    if OFF_A1H8(s[1]) != 0:
+        return (6 * 63 + (s[0] >> 3) * 28 + MAP_B1H1H7[s[1]]) * 62 + s[2] - adjust2
+    if OFF_A1H8(s[2]) != 0:
+        return 6 * 63 * 62 + 4 * 28 * 62 + (s[0] >> 3) * 7 * 28 + ((s[1] >> 3) - adjust1) * 28 + MAP_B1H1H7[s[2]]
+    return 6 * 63 * 62 + 4 * 28 * 62 + 4 * 7 * 28 + (s[0] >> 3) * 7 * 6 + ((s[1] >> 3) - adjust1) * 6 + ((s[2] >> 3) - adjust2)
+
+
+TB_SIZE = 31332
+
+
+# Values by index for one side to move. Indices of illegal positions repeat the value before them, which pairs best.
+def table(values, convert):
+    entries = [None] * TB_SIZE
+    for (wk, wx, bk), value in values.items():
+        idx = index((wk, wx, bk))
+        value = convert(value)
+        assert entries[idx] in (None, value), "two positions with the same index differ"
+        entries[idx] = value
+    last = next(value for value in entries if value is not None)
+    for idx, value in enumerate(entries):
+        if value is None:
+            entries[idx] = last
+        last = entries[idx]
+    return entries
+
+
+# DTZ values of wins stored as their place in a map, most common first. The map is written for wins, losses, cursed
+# wins and blessed losses, of which only wins occur here.
+def remap(values):
+    counts = Counter(plies - 1 for plies in values.values() if plies is not None)
+    order = sorted(counts, key=lambda value: (-counts[value], value))
+    place = {value: i for i, value in enumerate(order)}
+    dtz = table(values, lambda plies: 0 if plies is None else place[plies - 1])
+    return dtz, bytes([len(order)]) + bytes(order) + b"\0" * 3
+
+
+# The sizes record of one sub-table, and its sparse index, block lengths and blocks, compressed the way the real
+# generator does it: runs of values are replaced by symbol pairs, and the symbols are written in a canonical Huffman code.
+def compress(entries, flags):
+    if len(set(entries)) == 1:
+        return bytes([flags | FLAG_SINGLE_VALUE, entries[0]]), b"", b"", b""
+
+    # Each symbol is a value, or a pair of two earlier symbols standing for both in turn.
+    tree = [("leaf", value) for value in sorted(set(entries))]
+    length = [1] * len(tree)
+    leaf = {value: sym for sym, (_, value) in enumerate(tree)}
+    stream = [leaf[value] for value in entries]
+    while len(tree) < MAX_SYMBOLS:
+        counts = Counter(zip(stream, stream[1:]))
+        pairs = [(n, pair) for pair, n in counts.items() if length[pair[0]] + length[pair[1]] <= 256]
+        if not pairs:
+            break
+        n, pair = max(pairs)
+        if n < MIN_PAIR_COUNT:
+            break
+        sym = len(tree)
+        tree.append(("pair", pair))
+        length.append(length[pair[0]] + length[pair[1]])
+        merged = []
+        i = 0
+        while i < len(stream):
+            if i + 1 < len(stream) and (stream[i], stream[i + 1]) == pair:
+                merged.append(sym)
+                i += 2
+            else:
+                merged.append(stream[i])
+                i += 1
+        stream = merged
+
+    # Huffman code lengths of the symbols left in the stream.
+    counts = Counter(stream)
+    heap = [(n, sym, [sym]) for sym, n in counts.items()]
+    heapq.heapify(heap)
+    bits = dict.fromkeys(counts, 0)
+    while len(heap) > 1:
+        n1, key, syms1 = heapq.heappop(heap)
+        n2, _, syms2 = heapq.heappop(heap)
+        for sym in syms1 + syms2:
+            bits[sym] += 1
+        heapq.heappush(heap, (n1 + n2, key, syms1 + syms2))
+    min_len, max_len = min(bits.values()), max(bits.values())
+
+    # Symbols are numbered from the longest code to the shortest, which the canonical code needs, then the ones only
+    # found inside pairs.
+    order = sorted(counts, key=lambda sym: (-bits[sym], sym)) + [sym for sym in range(len(tree)) if sym not in counts]
+    number = {sym: i for i, sym in enumerate(order)}
+    lowest = [0] * (max_len - min_len + 1)
+    first = [0] * (max_len - min_len + 1)
+    for i in range(max_len - min_len - 1, -1, -1):
+        longer = sum(1 for sym in counts if bits[sym] == min_len + i + 1)
+        lowest[i] = lowest[i + 1] + longer
+        first[i] = (first[i + 1] + longer) // 2
+    code = {}
+    for sym in order[:len(counts)]:
+        i = bits[sym] - min_len
+        code[sym] = first[i] + number[sym] - lowest[i]
+
+    # Blocks take whole symbols until the next one no longer fits.
+    block_bits = (1 << BLOCK_LOG2) * 8
+    blocks = []
+    block, used = [], 0
+    for sym in stream:
+        if used + bits[sym] > block_bits:
+            blocks.append(block)
+            block, used = [], 0
+        block.append(sym)
+        used += bits[sym]
+    blocks.append(block)
+
+    data = b""
+    starts = []
+    values = 0
+    for block in blocks:
+        starts.append(values)
+        values += sum(length[sym] for sym in block)
+        packed, used = 0, 0
+        for sym in block:
+            packed = (packed << bits[sym]) | code[sym]
+            used += bits[sym]
+        data += (packed << (block_bits - used)).to_bytes(block_bits // 8, "big")
+    block_lengths = b"".join(struct.pack("<H", sum(length[sym] for sym in block) - 1) for block in blocks)
+
+    # Each sparse entry points at the middle of its span, which may lie past the last value.
+    span = 1 << SPAN_LOG2
+    sparse = b""
+    for k in range((len(entries) + span - 1) // span):
+        middle = k * span + span // 2
+        block = max(b for b, start in enumerate(starts) if start <= middle)
+        sparse += struct.pack("<IH", block, middle - starts[block])
+
+    sizes = bytes([flags, BLOCK_LOG2, SPAN_LOG2, 0]) + struct.pack("<I", len(blocks)) + bytes([max_len, min_len])
+    sizes += b"".join(struct.pack("<H", sym) for sym in lowest) + struct.pack("<H", len(tree))
+    for sym in order:
+        kind, value = tree[sym]
+        if kind == "leaf":
+            sizes += bytes([value & 0xFF, (value >> 8) | 0xF0, 0xFF])
+        else:
+            left, right = number[value[0]], number[value[1]]
+            sizes += bytes([left & 0xFF, (left >> 8) | (right & 0xF) << 4, right >> 4])
+    if len(tree) & 1:
+        sizes += b"\0"
+    return sizes, sparse, block_lengths, data
+
+
+# Both tables have different material for each side, which the first byte after the magic says, even for DTZ tables
+# that store only one of them.
+def write(path, magic, pieces, sides, dtz_map=b""):
+    header = magic + bytes([1, 0])
+    for piece in pieces:
+        header += bytes([piece | piece << 4])
+    if len(header) & 1:
+        header += b"\0"
+    parts = [compress(entries, flags) for entries, flags in sides]
+    out = header + b"".join(part[0] for part in parts) + dtz_map
+    if magic == DTZ_MAGIC and len(out) & 1:
+        out += b"\0"
+    out += b"".join(part[1] for part in parts) + b"".join(part[2] for part in parts)
+    for part in parts:
+        out += b"\0" * (-len(out) % 64) + part[3]
+    out += b"\0" * (-len(out) % 64) + b"\0" * 16
+    with open(path, "wb") as f:
+        f.write(out)
+
+
+def main():
+    here = os.path.dirname(os.path.abspath(__file__))
+    for name, piece in [("KQvK", QUEEN), ("KRvK", ROOK)]:
+        wtm, btm = solve(piece)
+        pieces = [KING, piece, KING | BLACK]
+        # WDL values are stored plus two: 4 is a win, 2 a draw and 0 a loss.
+        wdl_white = table(wtm, lambda plies: 2 if plies is None else 4)
+        wdl_black = table(btm, lambda plies: 2 if plies is None else 0)
+        write(os.path.join(here, name + ".rtbw"), WDL_MAGIC, pieces, [(wdl_white, 0), (wdl_black, 0)])
+        # DTZ tables keep only one side to move, here white, in plies less one. Draws are never looked up.
+        dtz_white, dtz_map = remap(wtm)
+        flags = FLAG_MAPPED | FLAG_WIN_PLIES | FLAG_LOSS_PLIES
+        write(os.path.join(here, name + ".rtbz"), DTZ_MAGIC, pieces, [(dtz_white, flags)], dtz_map)
+        print(name, "longest win", max(plies for plies in wtm.values() if plies is not None), "plies")
+
+
+if __name__ == "__main__":
+    main()
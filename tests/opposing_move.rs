@@ -0,0 +1,165 @@
+mod support;
+
+use std::{str::FromStr, time::{Duration, Instant}};
+
+use bevy::{ecs::event::ManualEventReader, prelude::*};
+
+use chesslite::chess::{AwaitMove, BoardChange, BookHolder, ChessPluginSettings, EngineFailed, EngineKind, Margins, MoveFetch, TablebaseHolder, get_opposing_move};
+use chesslite::chess::resign::DrawPolicy;
+use chesslite::chess::setup::StartPosition;
+use chesslite::chess::theme::BoardTheme;
+use chesslite::core::board::{Board, GameOverState, Piece, PieceColor, PieceType, Variant};
+use chesslite::core::square::Square;
+use chesslite::core::uci::{EngineError, EngineFallback};
+use support::FakeEngine;
+
+//The player has White and has opened with e4, so it is the engine's turn.
+fn app(engine: &FakeEngine, fallback: EngineFallback) -> App {
+    let mut board = Board::new();
+    let e4 = board.parse_uci("e2e4").unwrap().unwrap();
+    board.make_simple_move(&e4);
+    let mut engine_settings = engine.settings();
+    engine_settings.fallback = fallback;
+    let settings = ChessPluginSettings {
+        color: PieceColor::White,
+        engine: true,
+        engine_kind: EngineKind::Stockfish{skill: 20},
+        engine_settings,
+        book: None,
+        tablebase: None,
+        analysis: false,
+        time_control: None,
+        takebacks: false,
+        move_hints: false,
+        theme: BoardTheme::default(),
+        animation_duration: Duration::ZERO,
+        piece_set: "classic".to_string(),
+        sounds: None,
+        draw_policy: DrawPolicy::Never,
+        start_position: StartPosition::Standard,
+        variant: Variant::Standard,
+        setup_menu: false,
+        margins: Margins{left: 10.0, right: 20.0, bottom: 10.0, top: 10.0},
+    };
+    let mut app = App::new();
+    app
+    .add_plugins(MinimalPlugins)
+    .insert_resource(board)
+    .insert_resource(settings)
+    .insert_resource(BookHolder{book: None})
+    .insert_resource(TablebaseHolder{tablebase: None})
+    .add_event::<AwaitMove>()
+    .add_event::<BoardChange>()
+    .add_event::<EngineFailed>()
+    .add_systems(Update, get_opposing_move);
+    app
+}
+
+fn fetches(app: &mut App) -> usize {
+    app.world.query_filtered::<Entity, With<MoveFetch>>().iter(&app.world).count()
+}
+
+//What came out of the engine's search, once it is over.
+#[derive(Default)]
+struct Finished{
+    board_changes: usize,
+    failures: Vec<(EngineError, EngineFallback)>,
+}
+
+//Ask for a move and check that a search was started for it.
+fn ask_for_move(app: &mut App) {
+    app.world.send_event(AwaitMove);
+    app.update();
+    assert_eq!(fetches(app), 1, "the search should be running");
+}
+
+fn finish(app: &mut App) -> Finished {
+    let mut board_changes = ManualEventReader::<BoardChange>::default();
+    let mut failures = ManualEventReader::<EngineFailed>::default();
+    let mut finished = Finished::default();
+    let start = Instant::now();
+    while fetches(app) > 0 {
+        assert!(start.elapsed() < Duration::from_secs(10), "the search never finished");
+        std::thread::sleep(Duration::from_millis(5));
+        app.update();
+        finished.board_changes += board_changes.read(app.world.resource::<Events<BoardChange>>()).count();
+        finished.failures.extend(failures.read(app.world.resource::<Events<EngineFailed>>()).map(|failed| (failed.error.clone(), failed.fallback)));
+    }
+    finished
+}
+
+fn piece_at(app: &App, square: &str) -> Option<Piece> {
+    app.world.resource::<Board>()[Square::from_str(square).unwrap()]
+}
+
+#[test]
+fn plays_the_engine_move() {
+    let engine = FakeEngine::new("plays_the_engine_move", "info depth 1 score cp 0\nbestmove e7e5");
+    let mut app = app(&engine, EngineFallback::Forfeit);
+    ask_for_move(&mut app);
+    let finished = finish(&mut app);
+    assert_eq!(finished.board_changes, 1);
+    assert!(finished.failures.is_empty());
+    assert_eq!(piece_at(&app, "e5"), Some(Piece{piece: PieceType::Pawn, color: PieceColor::Black}));
+    assert_eq!(app.world.resource::<Board>().movelist.len(), 2);
+}
+
+#[test]
+fn recovers_from_one_crash() {
+    let engine = FakeEngine::new("recovers_from_one_crash", "crash go\nrestart\nbestmove c7c5");
+    let mut app = app(&engine, EngineFallback::Forfeit);
+    ask_for_move(&mut app);
+    let finished = finish(&mut app);
+    assert!(finished.failures.is_empty());
+    assert_eq!(piece_at(&app, "c5"), Some(Piece{piece: PieceType::Pawn, color: PieceColor::Black}));
+    assert_eq!(engine.runs(), 2);
+}
+
+#[test]
+fn builtin_engine_stands_in() {
+    let engine = FakeEngine::new("builtin_engine_stands_in", "crash go");
+    let mut app = app(&engine, EngineFallback::Builtin{depth: 1});
+    ask_for_move(&mut app);
+    let finished = finish(&mut app);
+    assert_eq!(finished.board_changes, 1);
+    assert_eq!(finished.failures, [(EngineError::Exited, EngineFallback::Builtin{depth: 1})]);
+    let board = app.world.resource::<Board>();
+    assert_eq!(board.movelist.len(), 2);
+    assert_eq!(board.movelist[1].color, PieceColor::Black);
+}
+
+#[test]
+fn random_move_stands_in() {
+    let engine = FakeEngine::new("random_move_stands_in", "bestmove e7e4");
+    let mut app = app(&engine, EngineFallback::Random);
+    ask_for_move(&mut app);
+    let finished = finish(&mut app);
+    assert!(matches!(finished.failures[..], [(EngineError::BadMove(_), EngineFallback::Random)]));
+    assert_eq!(app.world.resource::<Board>().movelist.len(), 2);
+}
+
+#[test]
+fn hung_engine_forfeits() {
+    let engine = FakeEngine::new("hung_engine_forfeits", "hang go");
+    let mut app = app(&engine, EngineFallback::Forfeit);
+    ask_for_move(&mut app);
+    let finished = finish(&mut app);
+    assert_eq!(finished.failures, [(EngineError::Timeout("bestmove".to_string()), EngineFallback::Forfeit)]);
+    let board = app.world.resource::<Board>();
+    assert_eq!(board.outcome, Some(GameOverState::Forfeit(PieceColor::Black)));
+    assert_eq!(board.movelist.len(), 1);
+}
+
+#[test]
+fn finished_game_keeps_its_result() {
+    let engine = FakeEngine::new("finished_game_keeps_its_result", "delay go 200\nbestmove e7e5");
+    let mut app = app(&engine, EngineFallback::Forfeit);
+    ask_for_move(&mut app);
+    //The player resigns while the engine is still thinking.
+    app.world.resource_mut::<Board>().outcome = Some(GameOverState::Resignation(PieceColor::White));
+    let finished = finish(&mut app);
+    assert_eq!(finished.board_changes, 0);
+    let board = app.world.resource::<Board>();
+    assert_eq!(board.outcome, Some(GameOverState::Resignation(PieceColor::White)));
+    assert_eq!(board.movelist.len(), 1);
+}
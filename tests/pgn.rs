@@ -0,0 +1,46 @@
+use chesslite::core::board::{Board, GameOverState, PieceColor};
+use chesslite::core::pgn::{result_token, write_pgn};
+
+#[test]
+fn writes_a_result_token_for_every_ending() {
+    use GameOverState::*;
+    use PieceColor::{Black, White};
+    let cases = [
+        (Ongoing, "*"),
+        (Checkmate(White), "1-0"),
+        (Checkmate(Black), "0-1"),
+        //Resigning, flagging and forfeiting lose for the color given.
+        (Resignation(White), "0-1"),
+        (Resignation(Black), "1-0"),
+        (Timeout(White), "0-1"),
+        (Forfeit(Black), "1-0"),
+        (KingOfTheHill(Black), "0-1"),
+        (ThreeCheck(White), "1-0"),
+        (Adjudication(Some(White)), "1-0"),
+        (Adjudication(None), "1/2-1/2"),
+        (AgreedDraw, "1/2-1/2"),
+        (Stalemate, "1/2-1/2"),
+        (Repetition, "1/2-1/2"),
+        (FiftyMoves, "1/2-1/2"),
+        (InsufficientMaterial, "1/2-1/2"),
+        (TimeoutVsInsufficientMaterial(White), "1/2-1/2"),
+    ];
+    for (outcome, token) in cases {
+        assert_eq!(result_token(outcome), token, "{:?}", outcome);
+    }
+}
+
+#[test]
+fn tags_resignations_and_agreed_draws() {
+    let mut board = Board::new();
+    board.outcome = Some(GameOverState::Resignation(PieceColor::White));
+    let pgn = write_pgn(&board, &[], ["Player", "Engine"], None);
+    assert!(pgn.contains("[Result \"0-1\"]"), "{}", pgn);
+    assert!(pgn.contains("[Termination \"normal\"]"), "{}", pgn);
+    assert!(pgn.trim_end().ends_with("0-1"), "{}", pgn);
+
+    board.outcome = Some(GameOverState::AgreedDraw);
+    let pgn = write_pgn(&board, &[], ["Player", "Engine"], None);
+    assert!(pgn.contains("[Result \"1/2-1/2\"]"), "{}", pgn);
+    assert!(pgn.trim_end().ends_with("1/2-1/2"), "{}", pgn);
+}
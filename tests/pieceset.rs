@@ -0,0 +1,35 @@
+use std::path::Path;
+
+use chesslite::chess::pieceset::{rasterize, PIECE_SET_FOLDER};
+
+const SIZE: u32 = 64;
+
+fn pixel(data: &[u8], x: u32, y: u32) -> [u8; 4] {
+    let at = ((y * SIZE + x) * 4) as usize;
+    data[at..at + 4].try_into().unwrap()
+}
+
+#[test]
+fn rasterizes_a_piece() {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("assets").join(PIECE_SET_FOLDER).join("simple/white/pawn.svg");
+    let image = rasterize(&path, SIZE);
+    assert_eq!((image.texture_descriptor.size.width, image.texture_descriptor.size.height), (SIZE, SIZE));
+    //Transparent around the piece, white inside it and outlined in black.
+    assert_eq!(pixel(&image.data, 0, 0)[3], 0);
+    assert_eq!(pixel(&image.data, SIZE / 2, SIZE / 16)[3], 0);
+    assert_eq!(pixel(&image.data, SIZE / 2, SIZE * 2 / 3), [255, 255, 255, 255]);
+    let column: Vec<[u8; 4]> = (0..SIZE).map(|y| pixel(&image.data, SIZE / 2, y)).collect();
+    assert!(column.iter().any(|&[r, g, b, a]| a == 255 && r.max(g).max(b) < 64), "no outline");
+}
+
+#[test]
+fn draws_every_svg_piece() {
+    for color in ["white", "black"] {
+        for piece in ["king", "queen", "rook", "bishop", "knight", "pawn"] {
+            let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("assets").join(PIECE_SET_FOLDER).join(format!("simple/{}/{}.svg", color, piece));
+            let image = rasterize(&path, SIZE);
+            let opaque = image.data.chunks(4).filter(|pixel| pixel[3] == 255).count();
+            assert!(opaque > (SIZE * SIZE / 8) as usize, "{} is blank", path.display());
+        }
+    }
+}
@@ -0,0 +1,35 @@
+use chesslite::chess::EngineKind;
+use chesslite::chess::resign::{DrawPolicy, accepts_draw};
+use chesslite::core::board::{Board, PieceColor};
+use chesslite::core::uci::EngineSettings;
+
+fn accepts(fen: &str, engine_color: PieceColor, policy: DrawPolicy) -> bool {
+    let board = Board::from_fen(fen).unwrap();
+    accepts_draw(&board, engine_color, policy, EngineKind::Builtin{depth: 2}, &EngineSettings::default())
+}
+
+//White has an extra queen.
+const WHITE_AHEAD: &str = "4k3/8/8/8/8/8/8/3QK3 w - - 0 1";
+const WHITE_AHEAD_BLACK_TO_MOVE: &str = "4k3/8/8/8/8/8/8/3QK3 b - - 0 1";
+
+#[test]
+fn always_and_never_ignore_the_position() {
+    assert!(accepts(WHITE_AHEAD, PieceColor::White, DrawPolicy::Always));
+    assert!(!accepts(WHITE_AHEAD, PieceColor::Black, DrawPolicy::Never));
+}
+
+#[test]
+fn evaluation_takes_a_draw_only_when_worse_off() {
+    //The engine is to move.
+    assert!(!accepts(WHITE_AHEAD, PieceColor::White, DrawPolicy::Evaluation(0)));
+    assert!(accepts(WHITE_AHEAD_BLACK_TO_MOVE, PieceColor::Black, DrawPolicy::Evaluation(0)));
+    //The player offers on their own move, so the score for the side to move is turned around for the engine.
+    assert!(accepts(WHITE_AHEAD, PieceColor::Black, DrawPolicy::Evaluation(0)));
+    assert!(!accepts(WHITE_AHEAD_BLACK_TO_MOVE, PieceColor::White, DrawPolicy::Evaluation(0)));
+}
+
+#[test]
+fn evaluation_threshold_is_in_centipawns() {
+    assert!(accepts(WHITE_AHEAD_BLACK_TO_MOVE, PieceColor::White, DrawPolicy::Evaluation(2000)));
+    assert!(!accepts(WHITE_AHEAD, PieceColor::Black, DrawPolicy::Evaluation(-2000)));
+}
@@ -0,0 +1,70 @@
+use chesslite::core::board::{Board, GameOverState, Move, PieceColor, PieceType, SimpleMove};
+
+//Fixtures are kept in the JSON the serde feature writes, so a change to it shows up here first.
+const BOARD_JSON: &str = include_str!("fixtures/serde/board.json");
+
+//Castles, takes en passant and promotes, in that order.
+const MOVES: [&str; 15] = ["e2e4", "d7d5", "e4e5", "f7f5", "e5f6", "g8h6", "f6g7", "c8d7", "g1f3", "b8c6", "f1e2", "e7e6", "e1g1", "d8e7", "g7f8q"];
+
+fn played() -> Board {
+    let mut board = Board::new();
+    for uci in MOVES {
+        let bmove = board.parse_uci(uci).unwrap().unwrap();
+        board.make_simple_move(&bmove);
+    }
+    board
+}
+
+#[test]
+fn writes_boards_as_the_fixture() {
+    let board = played();
+    assert_eq!(board.fen(), "r3kQ1r/pppbq2p/2n1p2n/3p4/8/5N2/PPPPBPPP/RNBQ1RK1 b kq - 0 8");
+    assert_eq!(serde_json::to_string_pretty(&board).unwrap(), BOARD_JSON.trim_end());
+}
+
+#[test]
+fn reads_boards_from_the_fixture() {
+    let board: Board = serde_json::from_str(BOARD_JSON).unwrap();
+    let expected = played();
+    assert_eq!(board.fen(), expected.fen());
+    assert_eq!(board.movelist, expected.movelist);
+    assert_eq!(board.lastmove, expected.lastmove);
+    let castle = board.movelist[12];
+    assert_eq!((castle.from.to_string(), castle.to.to_string()), ("e1".to_string(), "g1".to_string()));
+    assert!(board.movelist[4].capture);
+    assert_eq!(board.movelist[14].promotion, Some(PieceType::Queen));
+}
+
+#[test]
+fn writes_moves_with_named_squares() {
+    let bmove = SimpleMove {from: "e7".parse().unwrap(), to: "e8".parse().unwrap(), promotion: Some(PieceType::Queen)};
+    let json = r#"{"from":"e7","to":"e8","promotion":"Queen"}"#;
+    assert_eq!(serde_json::to_string(&bmove).unwrap(), json);
+    assert_eq!(serde_json::from_str::<SimpleMove>(json).unwrap(), bmove);
+
+    let board = played();
+    let capture: Move = board.movelist[4];
+    let json = r#"{"from":"e5","to":"f6","color":"White","piece":{"piece":"Pawn","color":"White"},"capture":true,"promotion":null}"#;
+    assert_eq!(serde_json::to_string(&capture).unwrap(), json);
+    assert_eq!(serde_json::from_str::<Move>(json).unwrap(), capture);
+
+    assert!(serde_json::from_str::<SimpleMove>(r#"{"from":"e9","to":"e8","promotion":null}"#).is_err());
+}
+
+#[test]
+fn writes_every_result() {
+    use GameOverState::*;
+    use PieceColor::{Black, White};
+    let outcomes = vec![
+        AgreedDraw, Stalemate, Checkmate(White), Resignation(Black), Adjudication(Some(White)), Adjudication(None),
+        Timeout(Black), TimeoutVsInsufficientMaterial(White), Repetition, FiftyMoves, InsufficientMaterial,
+        KingOfTheHill(Black), ThreeCheck(White), Forfeit(Black), Ongoing,
+    ];
+    let json = concat!(
+        r#"["AgreedDraw","Stalemate",{"Checkmate":"White"},{"Resignation":"Black"},{"Adjudication":"White"},{"Adjudication":null},"#,
+        r#"{"Timeout":"Black"},{"TimeoutVsInsufficientMaterial":"White"},"Repetition","FiftyMoves","InsufficientMaterial","#,
+        r#"{"KingOfTheHill":"Black"},{"ThreeCheck":"White"},{"Forfeit":"Black"},"Ongoing"]"#,
+    );
+    assert_eq!(serde_json::to_string(&outcomes).unwrap(), json);
+    assert_eq!(serde_json::from_str::<Vec<GameOverState>>(json).unwrap(), outcomes);
+}
@@ -0,0 +1,97 @@
+use std::{collections::HashMap, env, fs, io::{self, BufRead, Write}, process, thread, time::Duration};
+
+//A stand-in for a UCI engine in tests, answering the way a script tells it to.
+//
+//The script is read from the file next to the executable with the extension .script, so a test links the executable
+//into a folder of its own as e.g. engine, writes engine.script, and runs engine. Each line is one of
+//    info <text>            printed as "info <text>" when told to go
+//    bestmove <move>        the answer to go, 0000 when left out
+//    delay <command> <ms>   wait this long before answering uci, isready or go
+//    crash <command>        exit as soon as the command arrives
+//    hang <command>         never answer the command, or anything after it
+//A line reading "restart" ends the script for one run. The first run follows the first part, the second run the
+//second and so on, with the last part kept for any runs after that. Runs are counted in engine.runs, and every
+//command received is written to engine.log.
+
+#[derive(Default)]
+struct Script{
+    info: Vec<String>,
+    bestmove: Option<String>,
+    delays: HashMap<String, Duration>,
+    crashes: Vec<String>,
+    hangs: Vec<String>,
+}
+
+impl Script {
+    fn parse(lines: &[&str]) -> Script {
+        let mut script = Script::default();
+        for line in lines {
+            let (directive, rest) = line.split_once(' ').unwrap_or((line, ""));
+            let rest = rest.trim().to_string();
+            match directive {
+                "info" => script.info.push(rest),
+                "bestmove" => script.bestmove = Some(rest),
+                "delay" => {
+                    let (command, ms) = rest.split_once(' ').expect("delay needs a command and milliseconds");
+                    script.delays.insert(command.to_string(), Duration::from_millis(ms.trim().parse().expect("delay in milliseconds")));
+                },
+                "crash" => script.crashes.push(rest),
+                "hang" => script.hangs.push(rest),
+                _ => panic!("unknown line in the fake engine's script: {}", line),
+            }
+        }
+        script
+    }
+}
+
+fn main() {
+    let exe = env::current_exe().expect("the fake engine has no path");
+    let text = fs::read_to_string(exe.with_extension("script")).unwrap_or_default();
+    let mut parts = vec![Vec::new()];
+    for line in text.lines().map(str::trim).filter(|line| !line.is_empty()) {
+        if line == "restart" {
+            parts.push(Vec::new());
+        } else {
+            parts.last_mut().unwrap().push(line);
+        }
+    }
+
+    let runs_path = exe.with_extension("runs");
+    let run = fs::read_to_string(&runs_path).ok().and_then(|runs| runs.trim().parse::<usize>().ok()).unwrap_or(0);
+    fs::write(&runs_path, (run + 1).to_string()).expect("unable to count runs");
+    let script = Script::parse(&parts[run.min(parts.len() - 1)]);
+
+    let mut log = fs::OpenOptions::new().create(true).append(true).open(exe.with_extension("log")).expect("unable to open the log");
+    let mut stdout = io::stdout();
+    for line in io::stdin().lock().lines().map_while(Result::ok) {
+        writeln!(log, "{}", line).unwrap();
+        let command = line.split_whitespace().next().unwrap_or_default();
+        if script.crashes.iter().any(|crash| crash == command) {
+            process::exit(1);
+        }
+        if script.hangs.iter().any(|hang| hang == command) {
+            loop {
+                thread::sleep(Duration::from_secs(60));
+            }
+        }
+        if let Some(delay) = script.delays.get(command) {
+            thread::sleep(*delay);
+        }
+        match command {
+            "uci" => {
+                writeln!(stdout, "id name Fake").unwrap();
+                writeln!(stdout, "uciok").unwrap();
+            },
+            "isready" => writeln!(stdout, "readyok").unwrap(),
+            "go" => {
+                for info in &script.info {
+                    writeln!(stdout, "info {}", info).unwrap();
+                }
+                writeln!(stdout, "bestmove {}", script.bestmove.as_deref().unwrap_or("0000")).unwrap();
+            },
+            "quit" => return,
+            _ => {},
+        }
+        stdout.flush().unwrap();
+    }
+}
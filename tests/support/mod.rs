@@ -0,0 +1,53 @@
+#![allow(dead_code)]
+
+use std::{env, fs, path::PathBuf, time::Duration};
+
+use chesslite::core::uci::{EngineSettings, SearchLimit};
+
+//A copy of the fake-uci test engine with a script of its own. See tests/support/fake_uci.rs for what a script holds.
+pub struct FakeEngine{
+    dir: PathBuf,
+    path: PathBuf,
+}
+
+impl FakeEngine {
+    //`name` keeps tests that run at the same time apart, so it has to be different for each.
+    pub fn new(name: &str, script: &str) -> FakeEngine {
+        let dir = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join("fake-uci").join(name);
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join(format!("engine{}", env::consts::EXE_SUFFIX));
+        //A link rather than a copy, as an executable still open for writing can't be run.
+        if fs::hard_link(env!("CARGO_BIN_EXE_fake-uci"), &path).is_err() {
+            fs::copy(env!("CARGO_BIN_EXE_fake-uci"), &path).unwrap();
+        }
+        fs::write(path.with_extension("script"), script).unwrap();
+        FakeEngine {dir, path}
+    }
+
+    //Quick searches and a short timeout, so tests of a hung engine don't take long.
+    pub fn settings(&self) -> EngineSettings {
+        EngineSettings {
+            path: self.path.clone(),
+            limit: SearchLimit::MoveTime(Duration::from_millis(10)),
+            timeout: Duration::from_millis(300),
+            ..EngineSettings::default()
+        }
+    }
+
+    //How many times the engine has been started.
+    pub fn runs(&self) -> usize {
+        fs::read_to_string(self.path.with_extension("runs")).map_or(0, |runs| runs.trim().parse().unwrap())
+    }
+
+    //Every command the engine was sent, one to a line.
+    pub fn log(&self) -> String {
+        fs::read_to_string(self.path.with_extension("log")).unwrap_or_default()
+    }
+}
+
+impl Drop for FakeEngine {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.dir);
+    }
+}
@@ -0,0 +1,94 @@
+use std::fs;
+
+use chesslite::core::board::{Board, GameOverState, PieceColor};
+use chesslite::core::syzygy::{Tablebase, Wdl};
+
+//KQvK and KRvK tables written by tests/fixtures/syzygy/generate.py. The values below are known for these endings,
+//e.g. the longest wins take 10 and 16 moves.
+fn tablebase() -> Tablebase {
+    Tablebase::open(concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/syzygy")).unwrap()
+}
+
+fn board(fen: &str) -> Board {
+    Board::from_fen(fen).unwrap()
+}
+
+#[test]
+fn probes_wdl_and_dtz() {
+    let tablebase = tablebase();
+    let cases = [
+        //Qb8 and Qh7 both mate.
+        ("7k/8/6K1/8/8/8/8/1Q6 w - - 0 1", Wdl::Win, 1),
+        //The longest wins with a queen and with a rook.
+        ("7K/6Q1/8/8/8/3k4/8/8 w - - 0 1", Wdl::Win, 19),
+        ("8/7K/8/2R5/8/3k4/8/8 w - - 0 1", Wdl::Win, 31),
+        ("8/7K/8/2R5/8/8/2k5/8 b - - 0 1", Wdl::Loss, -32),
+        ("1Q5k/8/6K1/8/8/8/8/8 b - - 0 1", Wdl::Loss, -1),
+        //The queen is left hanging next to the king.
+        ("8/8/8/8/8/8/6Qk/K7 b - - 0 1", Wdl::Draw, 0),
+        ("k7/2Q5/1K6/8/8/8/8/8 b - - 0 1", Wdl::Draw, 0),
+        //Black has the queen, so the table is looked up with the colors swapped.
+        ("1q6/8/8/8/8/6k1/8/7K b - - 0 1", Wdl::Win, 1),
+    ];
+    for (fen, wdl, dtz) in cases {
+        let board = board(fen);
+        assert_eq!(tablebase.probe_wdl(&board), Some(wdl), "{}", fen);
+        assert_eq!(tablebase.probe_dtz(&board), Some(dtz), "{}", fen);
+    }
+}
+
+#[test]
+fn best_move_wins_fastest() {
+    let tablebase = tablebase();
+    let mut mate = board("7k/8/6K1/8/8/8/8/1Q6 w - - 0 1");
+    let bmove = tablebase.best_move(&mate).unwrap();
+    mate.make_simple_move(&bmove);
+    assert!(mate.is_check(PieceColor::Black) && !mate.any_legal_moves());
+
+    let mut rook = board("8/7K/8/2R5/8/3k4/8/8 w - - 0 1");
+    let bmove = tablebase.best_move(&rook).unwrap();
+    rook.make_simple_move(&bmove);
+    assert_eq!(tablebase.probe_dtz(&rook), Some(-30));
+}
+
+#[test]
+fn adjudicates_known_results() {
+    let tablebase = tablebase();
+    assert_eq!(tablebase.adjudicate(&board("7K/6Q1/8/8/8/3k4/8/8 w - - 0 1")), Some(GameOverState::Adjudication(Some(PieceColor::White))));
+    assert_eq!(tablebase.adjudicate(&board("8/7K/8/2R5/8/8/2k5/8 b - - 0 1")), Some(GameOverState::Adjudication(Some(PieceColor::White))));
+    assert_eq!(tablebase.adjudicate(&board("8/8/8/8/8/8/6Qk/K7 b - - 0 1")), Some(GameOverState::Adjudication(None)));
+}
+
+#[test]
+fn leaves_wins_the_50_move_rule_draws_alone() {
+    let tablebase = tablebase();
+    //Mate takes 31 plies, which still fits after 69 but not after 70.
+    assert_eq!(tablebase.adjudicate(&board("8/7K/8/2R5/8/3k4/8/8 w - - 69 80")), Some(GameOverState::Adjudication(Some(PieceColor::White))));
+    assert_eq!(tablebase.adjudicate(&board("8/7K/8/2R5/8/3k4/8/8 w - - 70 80")), None);
+    assert_eq!(tablebase.adjudicate(&board("8/7K/8/2R5/8/8/2k5/8 b - - 70 80")), None);
+}
+
+#[test]
+fn leaves_other_material_alone() {
+    let tablebase = tablebase();
+    assert_eq!(tablebase.max_pieces(), 3);
+    assert_eq!(tablebase.probe_wdl(&Board::new()), None);
+    //Three pieces, but no table for them.
+    assert_eq!(tablebase.probe_wdl(&board("7k/8/6K1/8/8/8/8/1B6 w - - 0 1")), None);
+    assert_eq!(tablebase.probe_wdl(&board("8/8/8/8/8/8/6Rk/K5R1 b - - 0 1")), None);
+    //Castling rights aren't in the tables, including Chess960 ones with the king off the e-file.
+    assert_eq!(tablebase.probe_wdl(&board("4k3/8/8/8/8/8/8/5KR1 w G - 0 1")), None);
+    assert!(tablebase.probe_wdl(&board("4k3/8/8/8/8/8/8/5KR1 w - - 0 1")).is_some());
+}
+
+#[test]
+fn reports_broken_tables() {
+    let dir = concat!(env!("CARGO_TARGET_TMPDIR"), "/syzygy-broken");
+    fs::create_dir_all(dir).unwrap();
+    fs::write(format!("{}/KQvK.rtbw", dir), [0u8; 80]).unwrap();
+    let error = Tablebase::open(dir).err().unwrap();
+    assert!(error.to_string().contains("KQvK.rtbw"), "{}", error);
+    fs::remove_dir_all(dir).unwrap();
+
+    assert!(Tablebase::open(concat!(env!("CARGO_TARGET_TMPDIR"), "/syzygy-missing")).is_err());
+}
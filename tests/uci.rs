@@ -0,0 +1,207 @@
+mod support;
+
+use std::{str::FromStr, time::{Duration, Instant}};
+
+use chesslite::core::board::{Board, GameOverState, MATE_SCORE, ParseError, Piece, PieceColor, PieceType};
+use chesslite::core::square::Square;
+use chesslite::core::uci::{EngineError, EngineFallback};
+use support::FakeEngine;
+
+fn square(name: &str) -> Square {
+    Square::from_str(name).unwrap()
+}
+
+fn piece(piece: PieceType, color: PieceColor) -> Option<Piece> {
+    Some(Piece{piece, color})
+}
+
+#[test]
+fn plays_the_scripted_move() {
+    let engine = FakeEngine::new("plays_the_scripted_move", "info depth 1 score cp 20\nbestmove e2e4");
+    let mut board = Board::new();
+    board.engine_move(&engine.settings(), 5).unwrap();
+    assert_eq!(board[square("e4")], piece(PieceType::Pawn, PieceColor::White));
+    assert_eq!(board.movelist.len(), 1);
+    assert_eq!(engine.runs(), 1);
+    let log = engine.log();
+    let commands: Vec<&str> = log.lines().collect();
+    assert_eq!(commands, [
+        "uci",
+        "setoption name Skill Level value 5",
+        "isready",
+        "position startpos",
+        "go movetime 10",
+    ]);
+}
+
+#[test]
+fn sends_the_moves_played() {
+    let engine = FakeEngine::new("sends_the_moves_played", "bestmove g8f6");
+    let mut board = Board::new();
+    for uci in ["g1f3", "g8f6", "f3g1", "f6g8", "g1f3"] {
+        let bmove = board.parse_uci(uci).unwrap().unwrap();
+        board.make_simple_move(&bmove);
+    }
+    board.engine_move(&engine.settings(), 20).unwrap();
+    //Everything since the start, so the engine knows g8f6 repeats the position a second time.
+    assert!(engine.log().lines().any(|line| line == "position startpos moves g1f3 g8f6 f3g1 f6g8 g1f3"), "{}", engine.log());
+}
+
+#[test]
+fn writes_positions_from_their_start() {
+    let fen = "4k3/8/8/8/8/8/4P3/4K2R w K - 0 1";
+    let mut board = Board::from_fen(fen).unwrap();
+    assert_eq!(board.uci_position(), format!("position fen {}", fen));
+    for uci in ["e1g1", "e8d8", "e2e4"] {
+        let bmove = board.parse_uci(uci).unwrap().unwrap();
+        board.make_simple_move(&bmove);
+    }
+    assert_eq!(board.uci_position(), format!("position fen {} moves e1g1 e8d8 e2e4", fen));
+
+    //Chess960 castling is the king taking its rook, however it was played.
+    let mut board = Board::from_fen("4k3/8/8/8/8/8/8/R4K1R w KQ - 0 1").unwrap();
+    let castle = board.parse_uci("f1h1").unwrap().unwrap();
+    board.make_simple_move(&castle);
+    assert_eq!(board[square("g1")], piece(PieceType::King, PieceColor::White));
+    assert_eq!(board.uci_position(), format!("position fen {} moves f1h1", board.start_fen.clone().unwrap()));
+}
+
+#[test]
+fn reads_the_deepest_score() {
+    let engine = FakeEngine::new("reads_the_deepest_score", "info depth 1 score cp 15\ninfo depth 2 score cp -40 pv e2e4\nbestmove e2e4");
+    assert_eq!(Board::new().engine_evaluation(&engine.settings()), Some(-40));
+
+    let engine = FakeEngine::new("reads_the_deepest_score_mate", "info depth 1 score cp 300\ninfo depth 5 score mate -3\nbestmove e2e4");
+    assert_eq!(Board::new().engine_evaluation(&engine.settings()), Some(-MATE_SCORE + 3));
+}
+
+#[test]
+fn restarts_after_a_crash() {
+    let engine = FakeEngine::new("restarts_after_a_crash", "crash go\nrestart\nbestmove d2d4");
+    let mut board = Board::new();
+    board.engine_move(&engine.settings(), 20).unwrap();
+    assert_eq!(board[square("d4")], piece(PieceType::Pawn, PieceColor::White));
+    assert_eq!(engine.runs(), 2);
+}
+
+#[test]
+fn restarts_after_hanging() {
+    let engine = FakeEngine::new("restarts_after_hanging", "hang go\nrestart\nbestmove g1f3");
+    let mut board = Board::new();
+    board.engine_move(&engine.settings(), 20).unwrap();
+    assert_eq!(board[square("f3")], piece(PieceType::Knight, PieceColor::White));
+    assert_eq!(engine.runs(), 2);
+}
+
+#[test]
+fn gives_up_on_an_engine_that_keeps_hanging() {
+    let engine = FakeEngine::new("gives_up_on_an_engine_that_keeps_hanging", "hang go");
+    let mut board = Board::new();
+    let start = Instant::now();
+    assert_eq!(board.engine_move(&engine.settings(), 20), Err(EngineError::Timeout("bestmove".to_string())));
+    //Two runs of the move time and the timeout, with plenty of room for a slow machine.
+    assert!(start.elapsed() < Duration::from_secs(5));
+    assert_eq!(engine.runs(), 2);
+    assert!(board.movelist.is_empty());
+}
+
+#[test]
+fn gives_up_on_an_engine_that_crashes_at_once() {
+    let engine = FakeEngine::new("gives_up_on_an_engine_that_crashes_at_once", "crash uci");
+    let mut board = Board::new();
+    assert_eq!(board.engine_move(&engine.settings(), 20), Err(EngineError::Exited));
+    assert_eq!(engine.runs(), 2);
+    assert_eq!(board.engine_evaluation(&engine.settings()), None);
+}
+
+#[test]
+fn waits_for_a_slow_engine() {
+    let engine = FakeEngine::new("waits_for_a_slow_engine", "delay uci 100\ndelay go 300\nbestmove e2e4");
+    let mut settings = engine.settings();
+    settings.timeout = Duration::from_secs(2);
+    let mut board = Board::new();
+    board.engine_move(&settings, 20).unwrap();
+    assert_eq!(board.movelist.len(), 1);
+    assert_eq!(engine.runs(), 1);
+}
+
+#[test]
+fn times_out_during_the_handshake() {
+    let engine = FakeEngine::new("times_out_during_the_handshake", "delay isready 1000\nbestmove e2e4");
+    let mut board = Board::new();
+    assert_eq!(board.engine_move(&engine.settings(), 20), Err(EngineError::Timeout("readyok".to_string())));
+    assert_eq!(engine.runs(), 2);
+}
+
+#[test]
+fn rejects_illegal_moves() {
+    let engine = FakeEngine::new("rejects_illegal_moves", "bestmove e2e5");
+    let mut board = Board::new();
+    assert_eq!(board.engine_move(&engine.settings(), 20), Err(EngineError::BadMove(ParseError::Illegal("e2e5".to_string()))));
+    //A wrong move is the engine's answer, not a failure to answer, so it isn't asked again.
+    assert_eq!(engine.runs(), 1);
+    assert!(board.movelist.is_empty());
+
+    let engine = FakeEngine::new("rejects_illegal_moves_malformed", "bestmove nonsense");
+    assert_eq!(board.engine_move(&engine.settings(), 20), Err(EngineError::BadMove(ParseError::Malformed("nonsense".to_string()))));
+}
+
+#[test]
+fn passes_only_without_moves() {
+    let engine = FakeEngine::new("passes_only_without_moves", "bestmove (none)");
+    let mut board = Board::new();
+    assert_eq!(board.engine_move(&engine.settings(), 20), Err(EngineError::BadMove(ParseError::Illegal("0000".to_string()))));
+
+    //Fool's mate, with White to move and mated.
+    let mut board = Board::from_fen("rnb1kbnr/pppp1ppp/8/4p3/6Pq/5P2/PPPPP2P/RNBQKBNR w KQkq - 1 3").unwrap();
+    assert_eq!(board.engine_move(&engine.settings(), 20), Ok(()));
+    assert!(board.movelist.is_empty());
+}
+
+#[test]
+fn reports_a_missing_engine() {
+    let mut settings = FakeEngine::new("reports_a_missing_engine", "").settings();
+    settings.path = settings.path.with_file_name("missing");
+    let mut board = Board::new();
+    assert!(matches!(board.engine_move(&settings, 20), Err(EngineError::Start(_))));
+}
+
+#[test]
+fn castles_in_chess960() {
+    let engine = FakeEngine::new("castles_in_chess960", "bestmove f1h1");
+    let mut board = Board::from_fen("4k3/8/8/8/8/8/8/R4K1R w KQ - 0 1").unwrap();
+    board.engine_move(&engine.settings(), 20).unwrap();
+    assert!(engine.log().lines().any(|line| line == "setoption name UCI_Chess960 value true"));
+    assert_eq!(board[square("g1")], piece(PieceType::King, PieceColor::White));
+    assert_eq!(board[square("f1")], piece(PieceType::Rook, PieceColor::White));
+    assert_eq!(board[square("h1")], None);
+}
+
+#[test]
+fn falls_back() {
+    let mut board = Board::new();
+    board.fallback_move(EngineFallback::Forfeit);
+    assert_eq!(board.outcome, Some(GameOverState::Forfeit(PieceColor::White)));
+    assert_eq!(board.outcome.unwrap().winner(), Some(PieceColor::Black));
+    assert!(board.movelist.is_empty());
+
+    for fallback in [EngineFallback::Random, EngineFallback::Builtin{depth: 2}] {
+        let mut board = Board::new();
+        board.fallback_move(fallback);
+        assert_eq!(board.movelist.len(), 1);
+        assert_eq!(board.outcome, None);
+    }
+}
+
+#[test]
+fn parses_fallbacks() {
+    assert_eq!("forfeit".parse(), Ok(EngineFallback::Forfeit));
+    assert_eq!("random".parse(), Ok(EngineFallback::Random));
+    assert_eq!("builtin".parse(), Ok(EngineFallback::default()));
+    assert_eq!("builtin:5".parse(), Ok(EngineFallback::Builtin{depth: 5}));
+    assert!("builtin:0".parse::<EngineFallback>().is_err());
+    assert!("stockfish".parse::<EngineFallback>().is_err());
+    for fallback in [EngineFallback::Forfeit, EngineFallback::Random, EngineFallback::Builtin{depth: 4}] {
+        assert_eq!(fallback.to_string().parse(), Ok(fallback));
+    }
+}